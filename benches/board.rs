@@ -0,0 +1,56 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use mnswpr_ui::{
+    exec::{GameCommand, GameCommandExecutor},
+    settings::Settings,
+};
+
+const SIZE: usize = 1000;
+// few enough mines for a single step to flood nearly the whole board
+const MINES: usize = SIZE;
+
+fn large_board() -> GameCommandExecutor {
+    let mut gcx = GameCommandExecutor::new(Settings::default());
+    gcx.generate(SIZE, SIZE, MINES);
+    gcx
+}
+
+fn open(c: &mut Criterion) {
+    let gcx = large_board();
+    let (x, y) = gcx
+        .mines_map
+        .positions()
+        .find(|pos| gcx.mines_map[*pos].is_blank())
+        .expect("a board this sparse has blank tiles");
+
+    c.bench_function("open 1000x1000", |b| {
+        b.iter_batched(
+            || gcx.clone(),
+            |mut gcx| gcx.exec_game_command(&GameCommand::Step(x, y)),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn neighbours(c: &mut Criterion) {
+    let gcx = large_board();
+
+    c.bench_function("neighbours 1000x1000", |b| {
+        b.iter(|| {
+            gcx.board_map
+                .positions()
+                .map(|(i, j)| gcx.neighbours(i, j).count())
+                .sum::<usize>()
+        })
+    });
+}
+
+fn generate(c: &mut Criterion) {
+    let mut gcx = GameCommandExecutor::new(Settings::default());
+
+    c.bench_function("generate 1000x1000", |b| {
+        b.iter(|| gcx.generate(black_box(SIZE), black_box(SIZE), MINES))
+    });
+}
+
+criterion_group!(benches, open, neighbours, generate);
+criterion_main!(benches);
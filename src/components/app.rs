@@ -1,9 +1,11 @@
 use yew::prelude::*;
 
-use crate::components::GameBoard;
+use crate::components::{use_auto_pause, GameBoard};
 
 #[function_component(Mnswpr)]
 pub fn mnswpr() -> Html {
+    use_auto_pause();
+
     html! {
         <main class="container">
             <GameBoard />
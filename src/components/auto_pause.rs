@@ -0,0 +1,66 @@
+use js_sys::Function;
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use yew::{platform::spawn_local, prelude::*};
+use yewdux::prelude::*;
+
+use crate::{
+    exec::GameCommandExecutor,
+    external_binding::{has_tauri, listen},
+};
+
+/// Pause the game whenever the window loses focus or the tab gets hidden,
+/// so the countdown won't run out while nobody is looking.
+#[hook]
+pub fn use_auto_pause() {
+    let dispatch = use_dispatch::<GameCommandExecutor>();
+
+    use_effect_with((), move |_| {
+        let window = web_sys::window().unwrap();
+        let document = window.document().unwrap();
+
+        let on_blur = {
+            let dispatch = dispatch.clone();
+            Closure::<dyn Fn(JsValue)>::new(move |_| {
+                dispatch.reduce_mut(|store| store.auto_pause())
+            })
+        };
+        let on_visibility_change = {
+            let document = document.clone();
+            Closure::<dyn Fn(JsValue)>::new(move |_| {
+                if document.hidden() {
+                    dispatch.reduce_mut(|store| store.auto_pause());
+                }
+            })
+        };
+
+        window
+            .add_event_listener_with_callback("blur", on_blur.as_ref().unchecked_ref())
+            .unwrap();
+        document
+            .add_event_listener_with_callback(
+                "visibilitychange",
+                on_visibility_change.as_ref().unchecked_ref(),
+            )
+            .unwrap();
+        // the webview doesn't always see the native window losing focus
+        let unlisten = has_tauri().then(|| listen("tauri://blur", &on_blur));
+
+        move || {
+            let _ = window
+                .remove_event_listener_with_callback("blur", on_blur.as_ref().unchecked_ref());
+            let _ = document.remove_event_listener_with_callback(
+                "visibilitychange",
+                on_visibility_change.as_ref().unchecked_ref(),
+            );
+            spawn_local(async move {
+                if let Some(unlisten) = unlisten {
+                    if let Ok(unlisten) = JsFuture::from(unlisten).await {
+                        let _ = unlisten.unchecked_into::<Function>().call0(&JsValue::NULL);
+                    }
+                }
+                drop(on_blur);
+            });
+        }
+    });
+}
@@ -3,10 +3,21 @@ use yew::prelude::*;
 use yewdux::prelude::*;
 
 use crate::{
-    components::CommandInputForm,
+    components::{focus_element, CommandInputForm, SettingsPanel},
     exec::{GameCommandExecutor, GameState, TileState},
+    keymap::{Action, Input},
+    puzzle::Puzzle,
+    topology::Surface,
 };
 
+// a mine marker, followed by the count when there is more than one on the tile
+fn multiple(marker: &str, count: usize) -> String {
+    match count {
+        1 => marker.to_string(),
+        _ => format!("{}{}", marker, count),
+    }
+}
+
 fn color(class: &str, text: &str) -> Html {
     html! {
         <span class={classes!["nes-text", class.to_string()]}>{text}</span>
@@ -17,6 +28,15 @@ fn color(class: &str, text: &str) -> Html {
 pub fn game_board() -> Html {
     let (gcx, _) = use_store::<GameCommandExecutor>();
 
+    if gcx.show_settings {
+        return html! {
+            <>
+                <SettingsPanel />
+                <CommandInputForm />
+            </>
+        };
+    }
+
     match gcx.current_state() {
         GameState::Init => html! {
             <>
@@ -24,13 +44,23 @@ pub fn game_board() -> Html {
                     <h2>{"Let's start!"}</h2>
                     <ul>
                         <li>{"Type "} {color("is-primary", "start")} {" to start playing, "}{color("is-primary", "restart")}{"/"}{color("is-primary", "reset")}{" to restart with different map."}</li>
+                        <li>{"Add a mode to start or restart with, "}{color("is-primary", "time-attack")}{" adds time for every safe step, "}{color("is-primary", "penalty")}{" takes time for wrong flags and refused chords, "}{color("is-primary", "lives 3")}{" lets you survive stepping on a few mines, "}{color("is-primary", "zen")}{" has no clock and no losing, "}{color("is-primary", "endless")}{" has no edges, scroll or "}{color("is-primary", "pan left 4")}{" to look around, "}{color("is-primary", "torus")}{"/"}{color("is-primary", "cylinder")}{"/"}{color("is-primary", "vertical-cylinder")}{" wrap the edges around, "}{color("is-primary", "hex")}{" plays on hexagons, "}{color("is-primary", "knight")}{"/"}{color("is-primary", "cross")}{"/"}{color("is-primary", "radius-2")}{" change which tiles a number counts, "}{color("is-primary", "multi 2")}{" hides up to 2 mines in a tile, toggle a flag again to count them, "}{color("is-primary", "classic")}{" to go back."}</li>
                         <li>{"You can use mouse or type these commands to play:"}
                             <ul>
                                 <li>{color("is-success", "sxx")}{" to step on a tile, replace xx with the tile coordinate, column go first."}</li>
-                                <li>{color("is-success", "fxx")}{" to flag the tile, "}{color("is-success", "uxx")}{" to unflag."}</li>
-                                <li>{color("is-success", "nxx")}{" to step to all the concealed neighbors of xx, works if xx tile already stepped, you lose if one of the neighbor tile conceal a bomb."}</li>
+                                <li>{color("is-success", "fxx")}{" to flag the tile, "}{color("is-success", "uxx")}{" to unflag, "}{color("is-success", "qxx")}{" to mark it with a question mark."}</li>
+                                <li>{"The tile can also be given as numbers, column then row, e.g. "}{color("is-success", "s3,5")}{", the axial coordinates on a hex board."}</li>
+                                <li>{color("is-success", "nxx")}{" to step to all the concealed neighbors of xx, works if xx tile already stepped and flagged neighbors match its number, you lose if one of the neighbor tile conceal a bomb."}</li>
+                                <li>{"With mouse, middle click, left+right click, or double click on a number to step its neighbors."}</li>
+                                <li>{"Or press "}{color("is-success", "Tab")}{" to move the cursor with arrow keys / hjkl, "}{color("is-success", "Space")}{" to step, "}{color("is-success", "f")}{" to flag, "}{color("is-success", "d")}{" to step the neighbors, "}{color("is-success", "Tab")}{" again to type commands."}</li>
+                                <li>{"Type "}{color("is-primary", "pause")}{" or press "}{color("is-primary", "Escape")}{" to pause and cover the board."}</li>
+                                <li>{"Type "}{color("is-primary", "settings")}{" to rebind the keys and mouse buttons."}</li>
                             </ul>
                         </li>
+                        <li>{color("is-primary", "puzzle 1")}{format!(" plays one of the {} handcrafted puzzles, finish it without guessing.", Puzzle::pack_len())}</li>
+                        <li>{color("is-primary", "daily")}{" plays the board of the day, the same for everyone, only the first try is recorded."}</li>
+                        <li>{color("is-primary", "export")}{" copies the board as text, "}{color("is-primary", "import 2x3 .*./... o-f/---")}{" plays it again, as pasted."}</li>
+                        <li>{color("is-primary", "alias x = t")}{" and "}{color("is-primary", "macro name r = s a$r s b$r")}{" define your own verbs, "}{color("is-primary", "profile name")}{" switches whose verbs are used."}</li>
                     </ul>
                 </div>
                 <CommandInputForm />
@@ -45,38 +75,160 @@ pub fn game_board() -> Html {
     }
 }
 
+#[derive(Properties, PartialEq)]
+struct ColumnLabelProps {
+    cols: usize,
+}
+
 #[function_component(ColumnLabel)]
-fn column_label() -> Html {
+fn column_label(props: &ColumnLabelProps) -> Html {
     html! {
         <tr class={classes!["mines-column-label"]}>
             <th class={classes!["mine-cell"]}>{""}</th>
-            <th class={classes!["mine-cell"]}>{"a"}</th>
-            <th class={classes!["mine-cell"]}>{"b"}</th>
-            <th class={classes!["mine-cell"]}>{"c"}</th>
-            <th class={classes!["mine-cell"]}>{"d"}</th>
-            <th class={classes!["mine-cell"]}>{"e"}</th>
-            <th class={classes!["mine-cell"]}>{"f"}</th>
-            <th class={classes!["mine-cell"]}>{"g"}</th>
-            <th class={classes!["mine-cell"]}>{"h"}</th>
+            { for (0..props.cols).map(|j| html! {
+                <th class={classes!["mine-cell"]}>{char::from(b'a' + j as u8)}</th>
+            }) }
         </tr>
     }
 }
 
+// board position of the tile under the mouse, from its 1-based data-x/data-y attributes
+fn tile_position(ev: &MouseEvent) -> (usize, usize) {
+    let tile = ev.target_unchecked_into::<HtmlElement>();
+    let x = tile
+        .get_attribute("data-x")
+        .unwrap()
+        .parse::<usize>()
+        .unwrap();
+    let y = tile
+        .get_attribute("data-y")
+        .unwrap()
+        .parse::<usize>()
+        .unwrap();
+    (y - 1, x - 1)
+}
+
+const LEFT_RIGHT_BUTTONS: u16 = 0b011;
+const MIDDLE_BUTTON: u16 = 0b100;
+
 #[function_component(Board)]
 fn draw_board() -> Html {
     let (hq, dispatch) = use_store::<GameCommandExecutor>();
     let mut btn_classes = vec!["nes-btn"];
-    let callback = dispatch.reduce_mut_callback_with(|store, ev: MouseEvent| {
-        let btn_nth = ev.button();
-        let button = ev.target_unchecked_into::<HtmlElement>();
-        let cmd = if btn_nth == 0 { "s" } else { "t" };
-        let x = button.get_attribute("data-x").unwrap();
-        let y = button.get_attribute("data-y").unwrap();
-
-        if let Ok(cmd) = store.parse_command(&format!("{}{}{}", cmd, x, y)) {
-            store.exec(&cmd);
+
+    // tile whose neighbours are shown pressed while a chord is held
+    let pressed = use_state(|| None::<(usize, usize)>);
+    // set once both left and right are held, kept until the next press
+    // so the click trailing the chord release is not taken as a step
+    let chording = use_mut_ref(|| false);
+    // tile already stepped when the clicks started, a double click only chords on it
+    // and not on a tile its own first click just opened
+    let chordable = use_mut_ref(|| None::<(usize, usize)>);
+
+    let callback = {
+        let chording = chording.clone();
+        let chordable = chordable.clone();
+        dispatch.reduce_mut_callback_with(move |store, ev: MouseEvent| {
+            ev.prevent_default();
+
+            let input = match (ev.type_().as_str(), ev.button()) {
+                _ if *chording.borrow() => return,
+                ("dblclick", _) if *chordable.borrow() != Some(tile_position(&ev)) => return,
+                ("dblclick", _) => Input::DoubleClick,
+                // right button fires both auxclick and contextmenu, only take the latter
+                ("auxclick", 1) | ("click", _) | ("contextmenu", _) => {
+                    Input::from_button(ev.button())
+                }
+                _ => return,
+            };
+            let (x, y) = tile_position(&ev);
+
+            if let Some(action) = store.settings.active().keymap.action(&input) {
+                store.perform(action, x, y).unwrap_or_default();
+            }
+        })
+    };
+
+    let mousedown_callback = {
+        let chording = chording.clone();
+        let pressed = pressed.clone();
+        let hq = hq.clone();
+        Callback::from(move |ev: MouseEvent| {
+            // the first press of a run of clicks
+            if ev.detail() <= 1 {
+                let pos = tile_position(&ev);
+                *chordable.borrow_mut() = (hq.board_map[pos] == TileState::Stepped).then_some(pos);
+            }
+            let buttons = ev.buttons();
+            *chording.borrow_mut() = buttons & LEFT_RIGHT_BUTTONS == LEFT_RIGHT_BUTTONS;
+            if *chording.borrow() || buttons & MIDDLE_BUTTON != 0 {
+                // also keeps the browser from starting middle click autoscroll
+                ev.prevent_default();
+                pressed.set(Some(tile_position(&ev)));
+            }
+        })
+    };
+
+    let mouseup_callback = {
+        let pressed = pressed.clone();
+        dispatch.reduce_mut_callback_with(move |store, ev: MouseEvent| {
+            if ev.buttons() != 0 {
+                return;
+            }
+            pressed.set(None);
+
+            if *chording.borrow() {
+                let (x, y) = tile_position(&ev);
+                let keymap = &store.settings.active().keymap;
+                if let Some(action) = keymap.action(&Input::LeftRightClick) {
+                    store.perform(action, x, y).unwrap_or_default();
+                }
+            }
+        })
+    };
+
+    let mouseleave_callback = {
+        let pressed = pressed.clone();
+        Callback::from(move |_: MouseEvent| pressed.set(None))
+    };
+
+    let pressed_tiles = pressed
+        .map(|(x, y)| hq.neighbours(x, y).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    // the wheel scrolls the view of an endless board, sideways with shift held
+    let wheel_callback = dispatch.reduce_mut_callback_with(|store, ev: WheelEvent| {
+        if store.endless.is_none() {
+            return;
         }
+        ev.prevent_default();
+        let step = |delta: f64| match delta {
+            d if d > 0.0 => 1,
+            d if d < 0.0 => -1,
+            _ => 0,
+        };
+        let (rows, cols) = if ev.shift_key() {
+            (0, step(ev.delta_y()))
+        } else {
+            (step(ev.delta_y()), step(ev.delta_x()))
+        };
+        store.pan(rows, cols);
+    });
 
+    let keyboard_callback = dispatch.reduce_mut_callback_with(|store, ev: KeyboardEvent| {
+        let Some(action) = store
+            .settings
+            .active()
+            .keymap
+            .action(&Input::key(&ev.key()))
+        else {
+            return;
+        };
+        let (x, y) = store.cursor;
+        match action {
+            Action::CommandLine => focus_element("cmd-input"),
+            action => store.perform(action, x, y).unwrap_or_default(),
+        }
         ev.prevent_default();
     });
 
@@ -87,40 +239,84 @@ fn draw_board() -> Html {
         btn_classes.push("is-disabled");
     }
 
-    let items = hq.board_map.iter().enumerate().map(|(y, row)| {
+    // cover every tile while paused, so pausing is not free thinking time
+    let paused = *hq.current_state() == GameState::Paused;
+
+    let tile = |y: usize, x: usize, cell: &TileState| -> Html {
+        if paused {
+            html! { <button class={btn_classes.clone()}></button> }
+        } else if cell.is_concealed() {
+            html! {
+                <button
+                    class={classes![btn_classes.clone(), pressed_tiles.contains(&(y, x)).then_some("is-pressed")]}
+                    onclick={callback.clone()}
+                    onauxclick={callback.clone()}
+                    ondblclick={callback.clone()}
+                    oncontextmenu={callback.clone()}
+                    onmousedown={mousedown_callback.clone()}
+                    onmouseup={mouseup_callback.clone()}
+                    data-x={(x+1).to_string()}
+                    data-y={(y+1).to_string()} >
+                    { if *cell == TileState::Questioned { "?" } else { "" } }
+                </button>
+            }
+        } else {
+            html! {
+                <div data-x={(x+1).to_string()} data-y={(y+1).to_string()}
+                    onclick={callback.clone()}
+                    onauxclick={callback.clone()}
+                    ondblclick={callback.clone()}
+                    oncontextmenu={callback.clone()}
+                    onmousedown={mousedown_callback.clone()}
+                    onmouseup={mouseup_callback.clone()} > {
+                    if let TileState::Flagged(count) = cell { multiple("🚩", *count as usize) }
+                    else if cell.clone() == TileState::Revealed { multiple("💣", hq.mines_at(y, x)) }
+                    else if cell.clone() == TileState::Detonated { "💥".to_string() }
+                    else if hq.mines_map[(y, x)].adjacent == 0 { "".to_string() }
+                    else { hq.mines_map[(y, x)].adjacent.to_string() }
+                } </div>
+            }
+        }
+    };
+    let cursor = |y: usize, x: usize| (hq.cursor == (y, x) && !paused).then_some("is-cursor");
+
+    // hex rows are offset by half a tile more on each row, so the axial
+    // neighbours of a tile are the 6 touching it
+    if hq.rules.surface == Surface::Hex {
+        let rows = hq.board_map.rows().enumerate().map(|(y, row)| {
+            html! {
+                <div class={classes!["hex-row"]} style={format!("--hex-row: {}", y)}>
+                <span class={classes!["mines-row-label"]}>{y+1}</span>
+                { for row.iter().enumerate().map(|(x, cell)| html! {
+                    <div class={classes!["mine-cell", "hex-cell", cursor(y, x)]}>{ tile(y, x, cell) }</div>
+                }) } </div>
+            }
+        });
+
+        return html! {
+            <div id="mines-board" tabindex="0" onkeydown={keyboard_callback} onwheel={wheel_callback}>
+                <div class={classes!["mines-field", "hex-field"]} onmouseleave={mouseleave_callback}>
+                    { rows.collect::<Html>() }
+                </div>
+            </div>
+        };
+    }
+
+    let items = hq.board_map.rows().enumerate().map(|(y, row)| {
         html! {
             <tr>
             <td class={classes!["mines-row-label"]}>{y+1}</td>
             { for row.iter().enumerate().map(|(x, cell)| html! {
-                <td class={classes!["mine-cell"]}> {
-                    if cell.clone() == TileState::Concealed {
-                        html! {
-                            <button
-                                class={btn_classes.clone()}
-                                onclick={callback.clone()}
-                                oncontextmenu={callback.clone()}
-                                data-x={(x+1).to_string()}
-                                data-y={(y+1).to_string()} >
-                            </button>
-                        }
-                    } else { html! {
-                        <div data-x={(x+1).to_string()} data-y={(y+1).to_string()} oncontextmenu={callback.clone()} > {
-                            if cell.clone() == TileState::Flagged { "🚩".to_string() }
-                            else if cell.clone() == TileState::Revealed { "💣".to_string() }
-                            else if cell.clone() == TileState::Detonated { "💥".to_string() }
-                            else if hq.mines_map[y][x] == 0 { "".to_string() }
-                            else { hq.mines_map[y][x].to_string() }
-                        } </div>
-                    }}
-                } </td>
+                <td class={classes!["mine-cell", cursor(y, x)]}>{ tile(y, x, cell) }</td>
             }) } </tr>
         }
     });
 
     html! {
-        <div class="nes-table-responsive">
-            <table class={classes!["mines-field", "nes-table", "is-bordered", "is-centered"]}>
-                <ColumnLabel />
+        <div id="mines-board" class="nes-table-responsive" tabindex="0" onkeydown={keyboard_callback} onwheel={wheel_callback}>
+            <table class={classes!["mines-field", "nes-table", "is-bordered", "is-centered"]}
+                onmouseleave={mouseleave_callback}>
+                <ColumnLabel cols={hq.size().1} />
                 <tbody>{ items.collect::<Html>() }</tbody>
            </table>
         </div>
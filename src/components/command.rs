@@ -3,8 +3,15 @@ use yew::prelude::*;
 use yewdux::prelude::*;
 
 use crate::{
-    components::{GameStartResetButton, TimerDisplay},
+    components::{
+        focus_element, is_focused, timer::seconds_and_tenths, GameStartResetButton, LivesDisplay,
+        MineCounter, TimerDisplay,
+    },
+    daily::DailyOutcome,
+    errors::GameError,
     exec::{GameCommandExecutor, GameState},
+    keymap::{Action, Input},
+    rules::GameMode,
 };
 
 #[function_component(CommandInputForm)]
@@ -14,12 +21,16 @@ pub fn command_input_form() -> Html {
 
     {
         let command_input_ref = command_input_ref.clone();
+        let show_settings = hq.show_settings;
         use_effect(move || {
-            command_input_ref
-                .cast::<HtmlInputElement>()
-                .unwrap()
-                .focus()
-                .unwrap();
+            // leave the focus alone while the player is navigating the board with keyboard
+            if !is_focused("mines-board") && !show_settings {
+                command_input_ref
+                    .cast::<HtmlInputElement>()
+                    .unwrap()
+                    .focus()
+                    .unwrap();
+            }
         });
     }
 
@@ -27,20 +38,71 @@ pub fn command_input_form() -> Html {
         if e.key() == "Enter" {
             let mut gcx = (*store).clone();
             let command_input: HtmlInputElement = e.target_unchecked_into();
-            gcx.parse_command(command_input.value().trim())
-                .map(|cmd| gcx.exec(&cmd))
-                .unwrap();
-            command_input.set_value("");
+            // the error is kept in the store and shown in place of the placeholder
+            let _ = gcx.run(command_input.value().trim());
+            match gcx.exported.take() {
+                // left selected, ready to be copied
+                Some(text) => {
+                    command_input.set_value(&text);
+                    command_input.select();
+                }
+                None => command_input.set_value(""),
+            }
             return gcx.into();
         }
         store
     });
 
+    let input_keydown = dispatch.reduce_mut_callback_with(|store, e: KeyboardEvent| {
+        if e.key() == "Tab" {
+            e.prevent_default();
+            focus_element("mines-board");
+        }
+
+        // only non character keys, so a key bound to pause can still be typed
+        let action = store.settings.active().keymap.action(&Input::key(&e.key()));
+        if e.key().chars().count() > 1 && action == Some(Action::Pause) {
+            e.prevent_default();
+            store.pause().unwrap_or_default();
+        }
+    });
+
     let placeholder = match *hq.current_state() {
-        GameState::Win => "YOU WIN!",
-        GameState::Lose => "GAME OVER",
-        GameState::Paused => "zzZ...",
-        _ => "type command...",
+        _ if hq.error != GameError::None => format!("{}!", hq.error),
+        GameState::Win if matches!(hq.rules.mode, GameMode::Puzzle(_)) => "SOLVED!".to_string(),
+        ref state @ (GameState::Win | GameState::Lose) if hq.rules.mode == GameMode::Daily => {
+            let headline = match state {
+                GameState::Win => format!(
+                    "YOU WIN! {}s",
+                    seconds_and_tenths(hq.result.elapsed.unwrap_or_default())
+                ),
+                _ => "GAME OVER".to_string(),
+            };
+            let first = match hq.daily.outcome {
+                Some(DailyOutcome::Won(elapsed)) => format!("won {}s", seconds_and_tenths(elapsed)),
+                _ => "lost".to_string(),
+            };
+            format!("{}, daily {} first try: {}", headline, hq.daily.date, first)
+        }
+        GameState::Win => format!(
+            "YOU WIN! {}s{}",
+            seconds_and_tenths(hq.result.elapsed.unwrap_or_default()),
+            if hq.result.assisted() {
+                " (assisted)"
+            } else {
+                ""
+            },
+        ),
+        GameState::Lose if hq.rules.mode == GameMode::Endless => {
+            format!("GAME OVER, {} tiles cleared", hq.result.explored)
+        }
+        GameState::Lose => "GAME OVER".to_string(),
+        GameState::Paused => "zzZ...".to_string(),
+        _ if hq.rules.mode == GameMode::Zen && hq.result.cleared > 0 => {
+            format!("{} cleared, type command...", hq.result.cleared)
+        }
+        GameState::Init if !hq.daily.attempted_today() => "type daily or a command...".to_string(),
+        _ => "type command...".to_string(),
     };
 
     html! {
@@ -50,7 +112,10 @@ pub fn command_input_form() -> Html {
                     ref={command_input_ref}
                     class={classes!["nes-input"]}
                     {placeholder}
-                    onkeypress={input_command} />
+                    onkeypress={input_command}
+                    onkeydown={input_keydown} />
+                <LivesDisplay />
+                <MineCounter />
                 <TimerDisplay />
                 <GameStartResetButton />
             </span>
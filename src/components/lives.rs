@@ -0,0 +1,21 @@
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+use crate::exec::GameCommandExecutor;
+
+#[function_component(LivesDisplay)]
+pub fn lives_display() -> Html {
+    let (gcx, _) = use_store::<GameCommandExecutor>();
+
+    let (Some(lives), Some(total)) = (gcx.lives, gcx.rules.lives) else {
+        return html! {};
+    };
+
+    html! {
+        <span id="lives-display">
+            { for (0..total).map(|i| html! {
+                <i class={classes!["nes-icon", "heart", "is-small", (i >= lives).then_some("is-empty")]}></i>
+            }) }
+        </span>
+    }
+}
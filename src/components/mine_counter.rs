@@ -0,0 +1,28 @@
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+use crate::exec::{GameCommandExecutor, GameState};
+
+#[function_component(MineCounter)]
+pub fn mine_counter() -> Html {
+    let (gcx, _) = use_store::<GameCommandExecutor>();
+    let mines_left = gcx.mines_left();
+
+    let display_class = match gcx.current_state() {
+        GameState::Init => "is-disabled",
+        _ if mines_left < 0 && gcx.endless.is_none() => "is-warning",
+        _ => "",
+    };
+
+    // the mines of an endless board can't be counted, its score is shown instead
+    let label = match gcx.endless {
+        Some(_) => format!("⛏{:02}", gcx.result.explored),
+        None => format!("💣{:02}", mines_left),
+    };
+
+    html! {
+        <button id="mine-counter" type="button" class={classes!["nes-btn", display_class]}>
+            {label}
+        </button>
+    }
+}
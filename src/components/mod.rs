@@ -1,11 +1,38 @@
+use wasm_bindgen::JsCast;
+
 pub mod app;
+pub mod auto_pause;
 pub mod board;
 pub mod command;
+pub mod lives;
+pub mod mine_counter;
+pub mod settings;
 pub mod start_button;
 pub mod timer;
 
 pub use app::Mnswpr;
+pub use auto_pause::use_auto_pause;
 pub use board::GameBoard;
 pub use command::CommandInputForm;
+pub use lives::LivesDisplay;
+pub use mine_counter::MineCounter;
+pub use settings::SettingsPanel;
 pub use start_button::GameStartResetButton;
 pub use timer::TimerDisplay;
+
+pub(crate) fn focus_element(id: &str) {
+    if let Some(el) = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|doc| doc.get_element_by_id(id))
+        .and_then(|el| el.dyn_into::<web_sys::HtmlElement>().ok())
+    {
+        let _ = el.focus();
+    }
+}
+
+pub(crate) fn is_focused(id: &str) -> bool {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|doc| doc.active_element())
+        .is_some_and(|el| el.id() == id)
+}
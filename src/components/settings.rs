@@ -0,0 +1,179 @@
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+use crate::{
+    exec::GameCommandExecutor,
+    keymap::{Action, Input},
+    settings::TimerMode,
+};
+
+#[derive(Properties, PartialEq)]
+struct BindingRowProps {
+    input: Input,
+    action: Option<Action>,
+}
+
+#[function_component(BindingRow)]
+fn binding_row(props: &BindingRowProps) -> Html {
+    let dispatch = use_dispatch::<GameCommandExecutor>();
+
+    let onchange = {
+        let input = props.input.clone();
+        dispatch.reduce_mut_callback_with(move |store, e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            let action = Action::from_label(&select.value());
+            store
+                .settings
+                .update(|profile| profile.keymap.bind(input.clone(), action));
+        })
+    };
+
+    html! {
+        <tr>
+            <td>{props.input.label()}</td>
+            <td>
+                <div class={classes!["nes-select"]}>
+                    <select {onchange}>
+                        <option value="" selected={props.action.is_none()}>{"none"}</option>
+                        { for Action::ALL.iter().map(|action| html! {
+                            <option value={action.label()} selected={props.action == Some(*action)}>
+                                {action.label()}
+                            </option>
+                        }) }
+                    </select>
+                </div>
+            </td>
+        </tr>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct OptionToggleProps {
+    label: AttrValue,
+    checked: bool,
+    onchange: Callback<bool>,
+}
+
+#[function_component(OptionToggle)]
+fn option_toggle(props: &OptionToggleProps) -> Html {
+    let onchange = {
+        let onchange = props.onchange.clone();
+        Callback::from(move |e: Event| {
+            let checkbox: HtmlInputElement = e.target_unchecked_into();
+            onchange.emit(checkbox.checked());
+        })
+    };
+
+    html! {
+        <label class="settings-option">
+            <input type="checkbox" class="nes-checkbox" checked={props.checked} {onchange} />
+            <span>{&props.label}</span>
+        </label>
+    }
+}
+
+#[function_component(SettingsPanel)]
+pub fn settings_panel() -> Html {
+    let (gcx, dispatch) = use_store::<GameCommandExecutor>();
+    let profile = gcx.settings.active();
+    let keymap = &profile.keymap;
+
+    let flag_mode_callback = dispatch.reduce_mut_callback_with(|store, checked: bool| {
+        store
+            .settings
+            .update(|profile| profile.keymap.set_flag_mode(checked));
+    });
+
+    let question_marks_callback = dispatch.reduce_mut_callback_with(|store, checked: bool| {
+        store
+            .settings
+            .update(|profile| profile.question_marks = checked);
+    });
+
+    let auto_flag_callback = dispatch.reduce_mut_callback_with(|store, checked: bool| {
+        store.settings.update(|profile| profile.auto_flag = checked);
+    });
+
+    let auto_pause_callback = dispatch.reduce_mut_callback_with(|store, checked: bool| {
+        store
+            .settings
+            .update(|profile| profile.auto_pause = checked);
+    });
+
+    let elapsed_timer_callback = dispatch.reduce_mut_callback_with(|store, checked: bool| {
+        store.settings.update(|profile| {
+            profile.timer_mode = if checked {
+                TimerMode::Elapsed
+            } else {
+                TimerMode::Countdown
+            }
+        });
+    });
+
+    let aggressive_chord_callback = dispatch.reduce_mut_callback_with(|store, checked: bool| {
+        store
+            .settings
+            .update(|profile| profile.aggressive_chord = checked);
+    });
+
+    let add_key_callback = dispatch.reduce_mut_callback_with(|store, e: KeyboardEvent| {
+        e.prevent_default();
+        let input = Input::key(&e.key());
+        if store.settings.active().keymap.action(&input).is_none() {
+            store
+                .settings
+                .update(|profile| profile.keymap.bind(input, Some(Action::Step)));
+        }
+    });
+
+    let close_callback = dispatch.reduce_mut_callback(|store| store.show_settings = false);
+
+    let mouse_rows = Input::MOUSE.iter().map(|input| {
+        html! { <BindingRow input={input.clone()} action={keymap.action(input)} /> }
+    });
+
+    let key_rows = keymap
+        .bindings
+        .iter()
+        .filter(|(input, _)| matches!(input, Input::Key(_)))
+        .map(|(input, action)| {
+            html! { <BindingRow input={input.clone()} action={Some(*action)} /> }
+        });
+
+    html! {
+        <div class={classes!["nes-container", "with-title", "is-rounded", "settings-panel"]}>
+            <p class="title">{format!("Settings ({})", gcx.settings.profile)}</p>
+            <OptionToggle label="Flag mode, left click to flag"
+                checked={keymap.flag_mode()}
+                onchange={flag_mode_callback} />
+            <OptionToggle label="Question marks, toggle a flag into ?"
+                checked={profile.question_marks}
+                onchange={question_marks_callback} />
+            <OptionToggle label="Auto-flag obvious mines"
+                checked={profile.auto_flag}
+                onchange={auto_flag_callback} />
+            <OptionToggle label="Auto-pause when the window loses focus"
+                checked={profile.auto_pause}
+                onchange={auto_pause_callback} />
+            <OptionToggle label="Show elapsed time instead of the countdown"
+                checked={profile.timer_mode == TimerMode::Elapsed}
+                onchange={elapsed_timer_callback} />
+            <OptionToggle label="Aggressive chord, step neighbours regardless of flags"
+                checked={profile.aggressive_chord}
+                onchange={aggressive_chord_callback} />
+            <table class={classes!["nes-table", "is-bordered"]}>
+                <tbody>
+                    { for mouse_rows }
+                    { for key_rows }
+                </tbody>
+            </table>
+            <input class={classes!["nes-input"]}
+                placeholder="press a key to bind it..."
+                onkeydown={add_key_callback} />
+            <button type="button" class={classes!["nes-btn"]} onclick={close_callback}>
+                {"Close"}
+            </button>
+        </div>
+    }
+}
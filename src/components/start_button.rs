@@ -7,8 +7,11 @@ use crate::exec::{GameCommandExecutor, GameState, SystemCommand, Transition};
 pub fn game_start_button() -> Html {
     let (gcx, dispatch) = use_store::<GameCommandExecutor>();
 
-    let gamestart_callback = dispatch
-        .reduce_mut_callback(move |store| store.exec(&Transition::Init(SystemCommand::Start)));
+    let gamestart_callback = dispatch.reduce_mut_callback(move |store| {
+        store
+            .exec(&Transition::Init(SystemCommand::Start))
+            .unwrap_or_default()
+    });
 
     let start_text = match gcx.current_state() {
         GameState::Init => "Start",
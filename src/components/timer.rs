@@ -5,53 +5,60 @@ use yew::prelude::*;
 use yewdux::prelude::*;
 
 use crate::{
-    current_seconds,
-    exec::{GameCommandExecutor, GameState, TimerState, TIME_LIMIT},
+    current_millis,
+    exec::{GameCommandExecutor, GameState, TimerState},
+    rules::GameMode,
+    settings::TimerMode,
 };
 
+pub(crate) fn seconds_and_tenths(millis: u64) -> String {
+    format!("{}.{}", millis / 1000, millis % 1000 / 100)
+}
+
 #[function_component(TimerDisplay)]
 pub fn timer_display() -> Html {
     let (gcx, dispatch) = use_store::<GameCommandExecutor>();
-    let clock = use_state(|| TIME_LIMIT);
+    // tenths of a second, re-renders the display while the timer runs
+    let tick = use_state_eq(|| 0);
     let raf = use_request_animation_frame();
-    let clock2display = clock.clone();
 
+    let clock = gcx.clock();
     let display_class = gcx.timer_display_class(
         &clock,
         "is-disabled",
+        "is-success",
         "is-warning",
         "is-danger",
         "is-primary",
     );
 
-    let pause_callback = {
-        let clock = clock.clone();
-        dispatch.reduce_mut_callback(move |store| {
-            store.timer_pause_toggle(*clock);
-        })
+    let label = match (gcx.rules.mode, gcx.settings.active().timer_mode) {
+        (GameMode::Zen, _) => "zen".to_string(),
+        (GameMode::Endless, _) => "∞".to_string(),
+        (GameMode::Puzzle(n), _) => format!("#{}", n),
+        (_, TimerMode::Countdown) => format!("{}:{:02}", clock / 60, clock % 60),
+        (_, TimerMode::Elapsed) => seconds_and_tenths(gcx.elapsed_millis()),
     };
 
+    let pause_callback = dispatch.reduce_mut_callback(|store| store.timer_pause_toggle());
+
     {
         let gcx_dep = gcx.clone();
-        use_effect_with(gcx_dep.timer_state.clone(), move |_| {
+        // time attack and penalty change the countdown while the timer keeps running
+        let deps = (gcx_dep.timer_state.clone(), gcx_dep.time_left);
+        use_effect_with(deps, move |_| {
             if &GameState::Init != gcx.current_state() {
                 raf.each(move |_| {
-                    let started_at = match gcx.timer_state {
-                        TimerState::Started(ts) => ts,
-                        _ => return RAFNext::Abort,
-                    };
-
-                    let elapsed = current_seconds().saturating_sub(started_at);
-
-                    let eta = gcx.time_left.saturating_sub(elapsed);
-                    if eta != *clock {
-                        clock.set(eta);
+                    if !matches!(gcx.timer_state, TimerState::Started(_)) {
+                        return RAFNext::Abort;
                     }
 
-                    if eta == 0 {
+                    tick.set(current_millis() / 100);
+
+                    if gcx.remaining_millis() == 0 {
                         dispatch.apply(|cgcx: Rc<GameCommandExecutor>| {
                             let mut new_gcx = (*cgcx).clone();
-                            new_gcx.timer_checkin(eta);
+                            new_gcx.timer_checkin();
                             new_gcx.into()
                         });
                         return RAFNext::Abort;
@@ -67,7 +74,7 @@ pub fn timer_display() -> Html {
 
     html! {
         <button id="timer-button" type="button" class={classes!["nes-btn", display_class]} onclick={pause_callback}>
-            {format!("{}:{:02}", *clock2display/60, *clock2display%60)}
+            {label}
         </button>
     }
 }
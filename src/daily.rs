@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use yewdux::storage::{self, Area};
+
+use crate::endless::mix;
+
+/// The last daily challenge played, kept in the local storage.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyRecord {
+    /// Local date of the daily, as `YYYY-MM-DD`.
+    pub date: String,
+    /// How its first attempt ended, `None` while it is still being played.
+    pub outcome: Option<DailyOutcome>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DailyOutcome {
+    /// Cleared after the given play time, in milliseconds.
+    Won(u64),
+    Lost,
+}
+
+impl DailyRecord {
+    pub fn load() -> Self {
+        storage::load::<Self>(Area::Local)
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        // same as the settings, not worth interrupting the game for
+        let _ = storage::save(self, Area::Local);
+    }
+
+    pub fn attempted_today(&self) -> bool {
+        self.date == today()
+    }
+
+    /// Note today's daily as attempted, keeping the outcome of an earlier attempt.
+    pub fn start(&mut self) {
+        let date = today();
+        if self.date != date {
+            self.date = date;
+            self.outcome = None;
+            self.save();
+        }
+    }
+
+    /// Keep the outcome of the first attempt only, replays don't count.
+    pub fn finish(&mut self, outcome: DailyOutcome) {
+        if self.outcome.is_none() {
+            self.outcome = Some(outcome);
+            self.save();
+        }
+    }
+
+    /// Seed of the daily board for the preset, the same for everyone on that date.
+    pub fn seed(&self, rows: usize, cols: usize, mines: usize) -> u64 {
+        self.date
+            .bytes()
+            .map(u64::from)
+            .chain([rows as u64, cols as u64, mines as u64])
+            .fold(0, |seed, n| mix(seed ^ n))
+    }
+}
+
+/// Tile indices below the given length, drawn from `seed` the same way on every
+/// build and platform, unlike the generators of `rand`.
+pub fn draws(seed: u64) -> impl FnMut(usize) -> usize {
+    let mut draw = 0u64;
+    move |len| {
+        draw += 1;
+        (mix(seed.wrapping_add(draw)) % len as u64) as usize
+    }
+}
+
+/// Local calendar date, as `YYYY-MM-DD`.
+pub fn today() -> String {
+    let date = js_sys::Date::new_0();
+    format!(
+        "{:04}-{:02}-{:02}",
+        date.get_full_year(),
+        date.get_month() + 1,
+        date.get_date()
+    )
+}
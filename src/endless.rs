@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+
+use crate::{
+    errors::GameError,
+    exec::{Cell, TileState},
+    grid::Grid,
+};
+
+/// Tiles along each side of a chunk.
+pub const CHUNK: usize = 16;
+/// Share of the tiles hiding a mine.
+const MINE_DENSITY: f64 = 0.18;
+// the start is kept clear this far around, so the first step opens some room
+// whatever the kernel
+const SAFE_START: i64 = 2;
+// an endless board has no edge to stop a flood fill, this does instead
+const MAX_OPEN: usize = 100_000;
+
+/// Position of a tile on an endless board, as `(row, column)`, the start being `(0, 0)`.
+pub type WorldPos = (i64, i64);
+
+pub(crate) fn mix(mut z: u64) -> u64 {
+    // splitmix64 finalizer
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn is_mine(seed: u64, (i, j): WorldPos) -> bool {
+    if i.abs() <= SAFE_START && j.abs() <= SAFE_START {
+        return false;
+    }
+    let roll = mix(mix(seed ^ i as u64) ^ j as u64);
+    roll < (MINE_DENSITY * u64::MAX as f64) as u64
+}
+
+fn offset((i, j): WorldPos, (di, dj): (isize, isize)) -> WorldPos {
+    (i + di as i64, j + dj as i64)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Chunk {
+    cells: Grid<Cell>,
+    tiles: Grid<TileState>,
+}
+
+impl Chunk {
+    // the mines of a chunk only depend on the seed, so its numbers can be
+    // counted without generating the chunks around it
+    fn generate(seed: u64, offsets: &[(isize, isize)], key: WorldPos) -> Self {
+        let corner = (key.0 * CHUNK as i64, key.1 * CHUNK as i64);
+        let mut cells = Grid::new(CHUNK, CHUNK, Cell::default());
+        for (i, j) in cells.positions() {
+            let pos = (corner.0 + i as i64, corner.1 + j as i64);
+            cells[(i, j)] = if is_mine(seed, pos) {
+                Cell {
+                    mines: 1,
+                    adjacent: 0,
+                }
+            } else {
+                Cell {
+                    mines: 0,
+                    adjacent: offsets
+                        .iter()
+                        .filter(|o| is_mine(seed, offset(pos, **o)))
+                        .count() as u8,
+                }
+            };
+        }
+        Self {
+            cells,
+            tiles: Grid::new(CHUNK, CHUNK, TileState::Concealed),
+        }
+    }
+}
+
+/// Board without edges, its chunks generated from the seed as play reaches them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndlessBoard {
+    seed: u64,
+    offsets: &'static [(isize, isize)],
+    chunks: HashMap<WorldPos, Chunk>,
+    /// Top left tile of the part of the board on screen.
+    pub origin: WorldPos,
+    /// Safe tiles stepped on, the score of the game.
+    pub cleared: usize,
+}
+
+impl EndlessBoard {
+    /// Board counting neighbours at `offsets`, with the start in the middle of a `view` sized screen.
+    pub fn new(seed: u64, offsets: &'static [(isize, isize)], view: (usize, usize)) -> Self {
+        Self {
+            seed,
+            offsets,
+            chunks: HashMap::new(),
+            origin: (-(view.0 as i64 / 2), -(view.1 as i64 / 2)),
+            cleared: 0,
+        }
+    }
+
+    /// Board position of tile `x, y` on screen.
+    pub fn world(&self, x: usize, y: usize) -> WorldPos {
+        (self.origin.0 + x as i64, self.origin.1 + y as i64)
+    }
+
+    fn chunk(&mut self, (i, j): WorldPos) -> (&mut Chunk, (usize, usize)) {
+        let size = CHUNK as i64;
+        let key = (i.div_euclid(size), j.div_euclid(size));
+        let (seed, offsets) = (self.seed, self.offsets);
+        let chunk = self
+            .chunks
+            .entry(key)
+            .or_insert_with(|| Chunk::generate(seed, offsets, key));
+        (
+            chunk,
+            (i.rem_euclid(size) as usize, j.rem_euclid(size) as usize),
+        )
+    }
+
+    pub fn cell(&mut self, pos: WorldPos) -> Cell {
+        let (chunk, local) = self.chunk(pos);
+        chunk.cells[local]
+    }
+
+    pub fn tile(&mut self, pos: WorldPos) -> TileState {
+        self.tile_mut(pos).clone()
+    }
+
+    pub fn tile_mut(&mut self, pos: WorldPos) -> &mut TileState {
+        let (chunk, local) = self.chunk(pos);
+        &mut chunk.tiles[local]
+    }
+
+    pub fn neighbours(&self, pos: WorldPos) -> impl Iterator<Item = WorldPos> {
+        self.offsets.iter().map(move |o| offset(pos, *o))
+    }
+
+    /// Step on `pos`, true if there was a mine under it.
+    pub fn step(&mut self, pos: WorldPos) -> bool {
+        if !self.tile(pos).is_concealed() {
+            return false;
+        }
+        if self.cell(pos).is_mine() {
+            *self.tile_mut(pos) = TileState::Detonated;
+            return true;
+        }
+        self.reveal(pos);
+        self.open(pos, MAX_OPEN);
+        false
+    }
+
+    /// Step on the concealed neighbours of a stepped tile, once its number is
+    /// satisfied with flags or regardless of them if `aggressive`,
+    /// giving the number of mines that went off.
+    pub fn chord(&mut self, pos: WorldPos, aggressive: bool) -> Result<usize, GameError> {
+        // same as the classic board, a flag or a mine must not chord
+        if self.tile(pos) != TileState::Stepped {
+            return Ok(0);
+        }
+        let marked = self
+            .neighbours(pos)
+            .filter(|n| matches!(self.tile(*n), TileState::Flagged(_) | TileState::Detonated))
+            .count();
+        if !aggressive && marked != self.cell(pos).adjacent as usize {
+            return Err(GameError::ChordRefused);
+        }
+
+        // every mine stepped on goes off, each costing a life of its own
+        let neighbours = self.neighbours(pos).collect::<Vec<_>>();
+        Ok(neighbours.into_iter().filter(|n| self.step(*n)).count())
+    }
+
+    fn reveal(&mut self, pos: WorldPos) {
+        *self.tile_mut(pos) = TileState::Stepped;
+        self.cleared += 1;
+    }
+
+    // reveal the blank area around `pos`, no more than `limit` tiles of it
+    fn open(&mut self, pos: WorldPos, limit: usize) {
+        let mut blanks = vec![pos];
+        let mut opened = 0;
+        while let Some(pos) = blanks.pop() {
+            if !self.cell(pos).is_blank() {
+                continue;
+            }
+            for n in self.neighbours(pos) {
+                if opened >= limit {
+                    return;
+                }
+                if !self.tile(n).is_concealed() {
+                    continue;
+                }
+                self.reveal(n);
+                opened += 1;
+                if self.cell(n).is_blank() {
+                    blanks.push(n);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::topology::Surface;
+
+    fn board(seed: u64) -> EndlessBoard {
+        EndlessBoard::new(seed, Surface::Flat.topology().surrounding(), (8, 8))
+    }
+
+    // a safe tile with some mines around, to chord against
+    fn surrounded(board: &mut EndlessBoard) -> WorldPos {
+        (0..)
+            .map(|i| (i / 64, i % 64))
+            .find(|pos| {
+                let cell = board.cell(*pos);
+                !cell.is_mine() && cell.adjacent >= 2
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn chunks_depend_on_nothing_but_the_seed() {
+        let offsets = Surface::Flat.topology().surrounding();
+        for key in [(0, 0), (-3, 7)] {
+            assert_eq!(
+                Chunk::generate(42, offsets, key),
+                Chunk::generate(42, offsets, key)
+            );
+            assert_ne!(
+                Chunk::generate(42, offsets, key),
+                Chunk::generate(43, offsets, key)
+            );
+        }
+        let (mut first, mut second) = (board(7), board(7));
+        // reached in another order, still the same board
+        for pos in [(40, -40), (0, 0)] {
+            first.cell(pos);
+        }
+        assert_eq!(second.cell((40, -40)), first.cell((40, -40)));
+    }
+
+    #[test]
+    fn numbers_count_the_mines_across_chunk_borders() {
+        let mut board = board(3);
+        let border = CHUNK as i64;
+        for pos in (-2..2).flat_map(|i| (-40..40).flat_map(move |j| [(border + i, j), (j, i)])) {
+            let cell = board.cell(pos);
+            assert_eq!(cell.is_mine(), is_mine(3, pos));
+            if !cell.is_mine() {
+                let mines = board.neighbours(pos).filter(|n| is_mine(3, *n)).count();
+                assert_eq!(cell.adjacent as usize, mines, "{:?}", pos);
+            }
+        }
+    }
+
+    #[test]
+    fn start_is_safe() {
+        for seed in 0..32 {
+            let mut board = board(seed);
+            for i in -SAFE_START..=SAFE_START {
+                for j in -SAFE_START..=SAFE_START {
+                    assert!(!board.cell((i, j)).is_mine());
+                }
+            }
+            assert!(!board.step((0, 0)));
+            assert_eq!(board.tile((0, 0)), TileState::Stepped);
+        }
+    }
+
+    #[test]
+    fn opening_stops_at_the_limit() {
+        let mut board = board(5);
+        board.reveal((0, 0));
+        board.open((0, 0), 3);
+        assert_eq!(board.cleared, 4);
+        board.open((0, 0), MAX_OPEN);
+        assert!(board.cleared > 4);
+    }
+
+    #[test]
+    fn chord_sets_off_every_mine_around() {
+        let mut board = board(9);
+        let pos = surrounded(&mut board);
+        *board.tile_mut(pos) = TileState::Stepped;
+        assert_eq!(board.chord(pos, false), Err(GameError::ChordRefused));
+        let adjacent = board.cell(pos).adjacent as usize;
+        assert_eq!(board.chord(pos, true), Ok(adjacent));
+        let detonated = board
+            .neighbours(pos)
+            .filter(|n| board.tile(*n) == TileState::Detonated)
+            .count();
+        assert_eq!(detonated, adjacent);
+    }
+}
@@ -4,4 +4,25 @@ pub enum GameError {
     None,
     UnknownCommand,
     InvalidArgument,
+    RecursiveMacro,
+    ChordRefused,
+    InvalidPuzzle,
+    UnsolvablePuzzle,
+    InvalidBoard,
+}
+
+impl std::fmt::Display for GameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            GameError::None => "",
+            GameError::UnknownCommand => "unknown command",
+            GameError::InvalidArgument => "invalid argument",
+            GameError::RecursiveMacro => "macro nested too deep",
+            GameError::ChordRefused => "flags don't match the number",
+            GameError::InvalidPuzzle => "invalid puzzle",
+            GameError::UnsolvablePuzzle => "puzzle can't be solved without guessing",
+            GameError::InvalidBoard => "invalid board",
+        };
+        f.write_str(message)
+    }
 }
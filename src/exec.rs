@@ -1,17 +1,41 @@
 use rand::Rng;
 use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
 use yew::platform::spawn_local;
 use yewdux::prelude::*;
 
-use crate::{current_seconds, errors::GameError, external_binding::invoke};
+use crate::{
+    current_millis,
+    daily::{self, DailyOutcome, DailyRecord},
+    endless::EndlessBoard,
+    errors::GameError,
+    external_binding::{invoke, write_text},
+    grid::{BitSet, Grid, Pos},
+    keymap::Action,
+    position::Position,
+    puzzle::Puzzle,
+    result::GameResult,
+    rules::{GameMode, Rules, TIME_BONUS, TIME_PENALTY},
+    settings::{Definition, Settings},
+    topology::Neighbours,
+};
 
-const THE_BOMB: i8 = 99;
 pub const TIME_LIMIT: u64 = 60 * 5;
+pub const BOARD_SIZE: (usize, usize) = (8, 8);
+pub const MINES: usize = 16;
 
 pub enum SystemCommand {
     Start,
     Restart,
+    NewGame(Rules),
     Exit,
+    Define(Definition),
+    Settings,
+    Pause,
+    /// Scroll the endless board view by rows and columns.
+    Pan(isize, isize),
+    Export,
+    Import(Box<Position>),
 }
 
 pub enum Command {
@@ -46,6 +70,7 @@ pub enum GameCommand {
     Flag(usize, usize),
     Unflag(usize, usize),
     Toggle(usize, usize),
+    Question(usize, usize),
 }
 
 impl TryFrom<[char; 3]> for GameCommand {
@@ -68,46 +93,149 @@ impl TryFrom<[char; 3]> for GameCommand {
             'u' => Ok(GameCommand::Unflag(j, i)),
             't' => Ok(GameCommand::Toggle(j, i)),
             'n' => Ok(GameCommand::NeighboursStep(j, i)),
+            'q' => Ok(GameCommand::Question(j, i)),
             _ => Err(GameError::UnknownCommand),
         }
     }
 }
 
+impl GameCommand {
+    /// Tile the command is played on.
+    pub fn pos(&self) -> Option<Pos> {
+        match *self {
+            GameCommand::None => None,
+            GameCommand::Step(x, y)
+            | GameCommand::NeighboursStep(x, y)
+            | GameCommand::Flag(x, y)
+            | GameCommand::Unflag(x, y)
+            | GameCommand::Toggle(x, y)
+            | GameCommand::Question(x, y) => Some((x, y)),
+        }
+    }
+
+    /// Parse the `s3,5` form, where the tile is given as numbers, column then row,
+    /// i.e. the axial `q,r` of a hex board.
+    pub fn from_axial(cmd: &str) -> Result<Self, GameError> {
+        let mut chars = cmd.chars();
+        let verb = chars.next().ok_or(GameError::UnknownCommand)?;
+        let (q, r) = chars
+            .as_str()
+            .split_once(',')
+            .ok_or(GameError::InvalidArgument)?;
+        let digit = |n: &str| {
+            n.parse::<u32>()
+                .ok()
+                .filter(|n| (1..=8).contains(n))
+                .and_then(|n| char::from_digit(n, 10))
+                .ok_or(GameError::InvalidArgument)
+        };
+        [verb, digit(q)?, digit(r)?].try_into()
+    }
+}
+
+/// What is under a tile, fixed once the board is generated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cell {
+    /// Mines hidden in the tile, more than one only with multi-mine tiles.
+    pub mines: u8,
+    /// Mines in the neighbouring tiles, the number shown once stepped on.
+    pub adjacent: u8,
+}
+
+impl Cell {
+    pub fn is_mine(&self) -> bool {
+        self.mines > 0
+    }
+
+    /// No mine in or around the tile, so its neighbours are safe to open.
+    pub fn is_blank(&self) -> bool {
+        !self.is_mine() && self.adjacent == 0
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub enum TileState {
     #[default]
     Concealed,
-    Flagged,
+    /// Flagged with the number of mines the player thinks are under it.
+    Flagged(u8),
     Stepped,
     Detonated,
     Revealed,
+    Questioned,
+}
+
+impl TileState {
+    /// Question marks are only a note for the player, the tile is still concealed.
+    pub fn is_concealed(&self) -> bool {
+        matches!(self, TileState::Concealed | TileState::Questioned)
+    }
+
+    /// Concealed -> Flagged(1..max) -> Questioned -> Concealed,
+    /// skipping the question mark if disabled.
+    pub fn toggled(&self, max: u8, question_marks: bool) -> Self {
+        match *self {
+            TileState::Concealed => TileState::Flagged(1),
+            TileState::Flagged(count) if count < max => TileState::Flagged(count + 1),
+            TileState::Flagged(_) if question_marks => TileState::Questioned,
+            TileState::Flagged(_) | TileState::Questioned => TileState::Concealed,
+            ref tile => tile.clone(),
+        }
+    }
+
+    pub fn questioned(&self) -> Self {
+        match self {
+            TileState::Concealed | TileState::Flagged(_) => TileState::Questioned,
+            TileState::Questioned => TileState::Concealed,
+            tile => tile.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub enum TimerState {
     #[default]
     Reset,
+    /// Running since the given timestamp, in milliseconds.
     Started(u64),
     Paused,
+    /// Frozen at the moment the game was won or lost.
+    Stopped,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct GameCommandExecutor {
-    pub mines_map: Vec<Vec<i8>>,
-    pub board_map: Vec<Vec<TileState>>,
+    pub mines_map: Grid<Cell>,
+    pub board_map: Grid<TileState>,
+    /// Tiles holding a mine, by their flat index.
+    mines: BitSet,
+    /// Safe tiles stepped on, by their flat index.
+    revealed: BitSet,
     pub timer_state: TimerState,
     state: GameState,
+    /// Countdown left as of the last (re)start of the timer, in milliseconds.
     pub time_left: u64,
+    /// Play time before the last (re)start of the timer, in milliseconds.
+    pub elapsed: u64,
+    pub settings: Settings,
+    pub cursor: (usize, usize),
+    pub show_settings: bool,
+    pub error: GameError,
+    pub result: GameResult,
+    pub rules: Rules,
+    pub lives: Option<u8>,
+    /// The whole board in endless mode, `mines_map` and `board_map` only hold the view of it.
+    pub endless: Option<EndlessBoard>,
+    pub daily: DailyRecord,
+    /// Text of the board from the last `export`, for the command line to hand over.
+    pub exported: Option<String>,
 }
 
 impl Store for GameCommandExecutor {
     fn new(_: &yewdux::Context) -> Self {
         Self {
-            mines_map: Vec::new(),
-            board_map: Vec::new(),
-            state: GameState::Init,
-            timer_state: TimerState::Reset,
-            time_left: TIME_LIMIT,
+            daily: DailyRecord::load(),
+            ..Self::new(Settings::load())
         }
     }
 
@@ -117,18 +245,141 @@ impl Store for GameCommandExecutor {
 }
 
 impl GameCommandExecutor {
-    fn init(&mut self) {
-        self.mines_map = Vec::new();
-        self.board_map = Vec::new();
-        self.timer_state = TimerState::Started(current_seconds());
-        self.time_left = TIME_LIMIT;
-        self.create_board_map();
-        self.generate_mines_map();
+    pub fn new(settings: Settings) -> Self {
+        Self {
+            mines_map: Grid::default(),
+            board_map: Grid::default(),
+            mines: BitSet::default(),
+            revealed: BitSet::default(),
+            state: GameState::Init,
+            timer_state: TimerState::Reset,
+            time_left: TIME_LIMIT * 1000,
+            elapsed: 0,
+            settings,
+            cursor: (0, 0),
+            show_settings: false,
+            error: GameError::None,
+            result: GameResult::default(),
+            rules: Rules::default(),
+            lives: None,
+            endless: None,
+            daily: DailyRecord::default(),
+            exported: None,
+        }
+    }
+
+    fn init(&mut self) -> Result<(), GameError> {
+        // only missing if the pack itself is broken, which must not pass for a puzzle
+        let puzzle = match self.rules.mode {
+            GameMode::Puzzle(n) => Some(Puzzle::from_pack(n)?),
+            _ => None,
+        };
+        // zen, endless and puzzles are played without a clock
+        self.timer_state = match self.rules.mode {
+            GameMode::Zen | GameMode::Endless | GameMode::Puzzle(_) => TimerState::Reset,
+            _ => TimerState::Started(current_millis()),
+        };
+        self.time_left = TIME_LIMIT * 1000;
+        self.elapsed = 0;
+        self.cursor = (0, 0);
+        self.lives = self.rules.lives;
+        self.result = GameResult {
+            mode: self.rules.mode,
+            ..GameResult::default()
+        };
+        if self.rules.mode == GameMode::Daily {
+            self.daily.start();
+        }
+        self.endless = (self.rules.mode == GameMode::Endless)
+            .then(|| EndlessBoard::new(rand::random(), self.offsets(), BOARD_SIZE));
+        match (self.rules.mode, puzzle) {
+            (GameMode::Endless, _) => self.refresh_view(),
+            (_, Some(puzzle)) => self.load(puzzle.mines_map, puzzle.board_map),
+            _ => self.generate(BOARD_SIZE.0, BOARD_SIZE.1, MINES),
+        }
+        Ok(())
     }
 
-    fn reinit(&mut self) {
-        self.init();
+    /// Play on the given board, e.g. a puzzle with some of it already revealed and flagged.
+    pub fn load(&mut self, mines_map: Grid<Cell>, board_map: Grid<TileState>) {
+        self.mines = BitSet::new(mines_map.len());
+        self.revealed = BitSet::new(board_map.len());
+        for pos in mines_map.positions() {
+            let index = mines_map.index_of(pos);
+            if mines_map[pos].is_mine() {
+                self.mines.insert(index);
+            } else if board_map[pos] == TileState::Stepped {
+                self.revealed.insert(index);
+            }
+        }
+        self.mines_map = mines_map;
+        self.board_map = board_map;
+    }
+
+    /// Deal a fresh `rows` x `cols` board hiding `mines` mines,
+    /// leaving the timer and the game state alone.
+    pub fn generate(&mut self, rows: usize, cols: usize, mines: usize) {
+        self.board_map = Grid::new(rows, cols, TileState::Concealed);
+        self.revealed = BitSet::new(rows * cols);
+        // the daily board depends on nothing but the date and the preset
+        match self.rules.mode {
+            GameMode::Daily => {
+                let seed = self.daily.seed(rows, cols, mines);
+                self.generate_mines_map(rows, cols, mines, daily::draws(seed));
+            }
+            _ => {
+                let mut rng = rand::thread_rng();
+                self.generate_mines_map(rows, cols, mines, |len| rng.gen_range(0..len));
+            }
+        }
+    }
+
+    fn reinit(&mut self) -> Result<(), GameError> {
+        self.init()?;
         self.transition_into(GameState::DrawBoard);
+        Ok(())
+    }
+
+    // the clipboard may be out of reach, the command line shows the text as well
+    fn export(&mut self) -> Result<(), GameError> {
+        // the endless board can't be written down, only its view
+        if self.endless.is_some() {
+            return Err(GameError::InvalidArgument);
+        }
+        let position = Position {
+            rules: self.rules.clone(),
+            mines_map: self.mines_map.clone(),
+            board_map: self.board_map.clone(),
+        };
+        let text = position.to_string();
+        let copied = text.clone();
+        spawn_local(async move {
+            if let Ok(promise) = write_text(&copied) {
+                let _ = JsFuture::from(promise).await;
+            }
+        });
+        self.exported = Some(text);
+        Ok(())
+    }
+
+    /// Play on an imported board, picking up where it was exported.
+    fn import(&mut self, position: &Position) -> Result<(), GameError> {
+        self.rules = position.rules.clone();
+        self.reinit()?;
+        self.load(position.mines_map.clone(), position.board_map.clone());
+        self.count_adjacent();
+        if self
+            .board_map
+            .iter()
+            .any(|tile| *tile == TileState::Detonated)
+        {
+            self.stop_timer();
+            self.transition_into(GameState::Lose);
+        } else if self.considered_win() {
+            self.stop_timer();
+            self.transition_into(GameState::Win);
+        }
+        Ok(())
     }
 
     fn exit() {
@@ -137,25 +388,72 @@ impl GameCommandExecutor {
         });
     }
 
-    pub fn timer_pause_toggle(&mut self, clock: u64) {
+    pub fn timer_pause_toggle(&mut self) {
         match self.timer_state {
             TimerState::Started(_) => {
-                self.time_left = clock;
+                self.fold_timer();
                 self.transition_into(GameState::Paused);
                 self.timer_state = TimerState::Paused;
             }
             TimerState::Paused => {
                 self.transition_into(GameState::DrawBoard);
-                self.timer_state = TimerState::Started(current_seconds());
+                self.timer_state = TimerState::Started(current_millis());
             }
             _ => {}
         };
     }
 
+    // move the time spent since the timer (re)started into `elapsed` and `time_left`
+    fn fold_timer(&mut self) {
+        if let TimerState::Started(started_at) = self.timer_state {
+            let now = current_millis();
+            let run = now.saturating_sub(started_at);
+            self.elapsed += run;
+            self.time_left = self.time_left.saturating_sub(run);
+            self.timer_state = TimerState::Started(now);
+        }
+    }
+
+    // freeze the timer and keep the final time in the result
+    fn stop_timer(&mut self) {
+        self.fold_timer();
+        self.timer_state = TimerState::Stopped;
+        self.result.elapsed = Some(self.elapsed);
+        self.result.remaining = Some(self.time_left);
+    }
+
+    fn since_started(&self) -> u64 {
+        match self.timer_state {
+            TimerState::Started(started_at) => current_millis().saturating_sub(started_at),
+            _ => 0,
+        }
+    }
+
+    pub fn remaining_millis(&self) -> u64 {
+        self.time_left.saturating_sub(self.since_started())
+    }
+
+    pub fn elapsed_millis(&self) -> u64 {
+        self.elapsed + self.since_started()
+    }
+
+    /// Whole seconds left on the countdown as of now.
+    pub fn clock(&self) -> u64 {
+        self.remaining_millis().div_ceil(1000)
+    }
+
+    /// Pause a running game when the player looks away, if the profile asks for it.
+    pub fn auto_pause(&mut self) {
+        if self.settings.active().auto_pause && matches!(self.timer_state, TimerState::Started(_)) {
+            self.timer_pause_toggle();
+        }
+    }
+
     pub fn timer_display_class<'c>(
         &self,
         clock: &u64,
         reset: &'c str,
+        stopped: &'c str,
         warning: &'c str,
         danger: &'c str,
         default: &'c str,
@@ -163,19 +461,34 @@ impl GameCommandExecutor {
         match *clock {
             _ if self.timer_state == TimerState::Reset => reset,
             _ if self.timer_state == TimerState::Paused => "",
+            // the final time, however little was left of the countdown
+            _ if self.timer_state == TimerState::Stopped => stopped,
             val if val <= (TIME_LIMIT / 4) => danger,
             val if val <= (TIME_LIMIT / 2) => warning,
             _ => default,
         }
     }
 
-    pub fn exec(&mut self, cmd: &Transition) {
+    pub fn exec(&mut self, cmd: &Transition) -> Result<(), GameError> {
         match cmd {
             Transition::Init(SystemCommand::Start)
             | Transition::DrawBoard(Command::System(SystemCommand::Restart))
             | Transition::Lose(SystemCommand::Restart)
             | Transition::Paused(SystemCommand::Restart)
-            | Transition::Win(SystemCommand::Restart) => self.reinit(),
+            | Transition::Win(SystemCommand::Restart) => self.reinit()?,
+
+            Transition::Init(SystemCommand::NewGame(rules))
+            | Transition::DrawBoard(Command::System(SystemCommand::NewGame(rules)))
+            | Transition::Lose(SystemCommand::NewGame(rules))
+            | Transition::Paused(SystemCommand::NewGame(rules))
+            | Transition::Win(SystemCommand::NewGame(rules)) => {
+                let rules = std::mem::replace(&mut self.rules, rules.clone());
+                // keep to the current game when the new one can't be dealt
+                if let Err(err) = self.reinit() {
+                    self.rules = rules;
+                    return Err(err);
+                }
+            }
 
             Transition::Init(SystemCommand::Exit)
             | Transition::DrawBoard(Command::System(SystemCommand::Exit))
@@ -183,7 +496,35 @@ impl GameCommandExecutor {
             | Transition::Paused(SystemCommand::Exit)
             | Transition::Win(SystemCommand::Exit) => Self::exit(),
 
-            Transition::DrawBoard(Command::Game(cmd)) => self.exec_game_command(cmd),
+            Transition::Init(SystemCommand::Define(def))
+            | Transition::DrawBoard(Command::System(SystemCommand::Define(def)))
+            | Transition::Lose(SystemCommand::Define(def))
+            | Transition::Paused(SystemCommand::Define(def))
+            | Transition::Win(SystemCommand::Define(def)) => self.settings.define(def),
+
+            Transition::Init(SystemCommand::Settings)
+            | Transition::DrawBoard(Command::System(SystemCommand::Settings))
+            | Transition::Lose(SystemCommand::Settings)
+            | Transition::Paused(SystemCommand::Settings)
+            | Transition::Win(SystemCommand::Settings) => self.show_settings = !self.show_settings,
+
+            Transition::DrawBoard(Command::System(SystemCommand::Pause))
+            | Transition::Paused(SystemCommand::Pause) => self.timer_pause_toggle(),
+
+            Transition::DrawBoard(Command::System(SystemCommand::Pan(rows, cols)))
+            | Transition::Lose(SystemCommand::Pan(rows, cols)) => self.pan(*rows, *cols),
+
+            Transition::DrawBoard(Command::System(SystemCommand::Export))
+            | Transition::Lose(SystemCommand::Export)
+            | Transition::Win(SystemCommand::Export) => self.export()?,
+
+            Transition::Init(SystemCommand::Import(position))
+            | Transition::DrawBoard(Command::System(SystemCommand::Import(position)))
+            | Transition::Lose(SystemCommand::Import(position))
+            | Transition::Paused(SystemCommand::Import(position))
+            | Transition::Win(SystemCommand::Import(position)) => self.import(position)?,
+
+            Transition::DrawBoard(Command::Game(cmd)) => self.exec_game_command(cmd)?,
 
             Transition::Init(_)
             | Transition::DrawBoard(Command::System(_))
@@ -191,47 +532,334 @@ impl GameCommandExecutor {
             | Transition::Paused(_)
             | Transition::Win(_) => {}
         }
+        Ok(())
     }
 
-    pub fn exec_game_command(&mut self, cmd: &GameCommand) {
+    pub fn exec_game_command(&mut self, cmd: &GameCommand) -> Result<(), GameError> {
+        // typed tiles reach as far as the largest board, not necessarily this one
+        let (rows, cols) = self.size();
+        if cmd.pos().is_some_and(|(x, y)| x >= rows || y >= cols) {
+            return Err(GameError::InvalidArgument);
+        }
+        if self.endless.is_some() {
+            return self.exec_endless_command(cmd);
+        }
         match cmd {
             GameCommand::None => {}
             GameCommand::Step(x, y) => self.step(*x, *y),
-            GameCommand::NeighboursStep(x, y) => self.neighbours_step(*x, *y),
+            GameCommand::NeighboursStep(x, y) => self.neighbours_step(*x, *y)?,
             GameCommand::Flag(x, y) => self.flag(*x, *y),
             GameCommand::Unflag(x, y) => self.unflag(*x, *y),
             GameCommand::Toggle(x, y) => self.toggle_flag(*x, *y),
+            GameCommand::Question(x, y) => self.question(*x, *y),
+        }
+        if matches!(cmd, GameCommand::Step(..) | GameCommand::NeighboursStep(..))
+            && self.settings.active().auto_flag
+            && self.state == GameState::DrawBoard
+        {
+            self.auto_flag();
+        }
+        // dealt once the command is done, so a chord doesn't carry over to the new board
+        if self.rules.mode == GameMode::Zen && self.considered_win() {
+            self.next_board();
+        }
+        Ok(())
+    }
+
+    // endless games are played on the whole board, then the view is refreshed from it
+    fn exec_endless_command(&mut self, cmd: &GameCommand) -> Result<(), GameError> {
+        let aggressive = self.settings.active().aggressive_chord;
+        let question_marks = self.settings.active().question_marks;
+        let Some(board) = self.endless.as_mut() else {
+            return Ok(());
+        };
+        let hits = match *cmd {
+            GameCommand::None => 0,
+            GameCommand::Step(x, y) => usize::from(board.step(board.world(x, y))),
+            GameCommand::NeighboursStep(x, y) => board.chord(board.world(x, y), aggressive)?,
+            GameCommand::Flag(x, y) => {
+                let tile = board.tile_mut(board.world(x, y));
+                if tile.is_concealed() {
+                    *tile = TileState::Flagged(1);
+                }
+                0
+            }
+            GameCommand::Unflag(x, y) => {
+                let tile = board.tile_mut(board.world(x, y));
+                if let TileState::Flagged(_) = tile {
+                    *tile = TileState::Concealed;
+                }
+                0
+            }
+            GameCommand::Toggle(x, y) => {
+                let tile = board.tile_mut(board.world(x, y));
+                *tile = tile.toggled(1, question_marks);
+                0
+            }
+            GameCommand::Question(x, y) => {
+                let tile = board.tile_mut(board.world(x, y));
+                *tile = tile.questioned();
+                0
+            }
+        };
+        if (0..hits).any(|_| !self.survive_hit()) {
+            self.transition_into(GameState::Lose);
+        }
+        self.refresh_view();
+        Ok(())
+    }
+
+    /// Scroll the view of the endless board by `rows` and `cols` tiles.
+    pub fn pan(&mut self, rows: isize, cols: isize) {
+        if let Some(board) = self.endless.as_mut() {
+            board.origin.0 += rows as i64;
+            board.origin.1 += cols as i64;
+            self.refresh_view();
+        }
+    }
+
+    // copy the part of the endless board on screen into the maps the board is drawn from,
+    // showing the mines around once the game is lost
+    fn refresh_view(&mut self) {
+        let lost = self.state == GameState::Lose;
+        let Some(board) = self.endless.as_mut() else {
+            return;
+        };
+        let (rows, cols) = BOARD_SIZE;
+        let mut mines_map = Grid::new(rows, cols, Cell::default());
+        let mut board_map = Grid::new(rows, cols, TileState::Concealed);
+        for (x, y) in board_map.positions() {
+            let pos = board.world(x, y);
+            let cell = board.cell(pos);
+            let tile = board.tile(pos);
+            board_map[(x, y)] = match tile {
+                tile if lost && cell.is_mine() && tile.is_concealed() => TileState::Revealed,
+                tile => tile,
+            };
+            mines_map[(x, y)] = cell;
+        }
+        self.mines_map = mines_map;
+        self.board_map = board_map;
+        self.result.explored = board.cleared;
+    }
+
+    /// Expand the macros in `input` with the active profile, then parse and execute
+    /// each of the resulting commands in order.
+    pub fn run(&mut self, input: &str) -> Result<(), GameError> {
+        let result = self.settings.active().expand(input).and_then(|cmds| {
+            // a typo late in a macro shouldn't leave it half played
+            let commands = cmds
+                .iter()
+                .map(|cmd| self.parse_command(cmd))
+                .collect::<Result<Vec<_>, _>>()?;
+            commands.into_iter().try_for_each(|c| {
+                let transition = self.transition(c)?;
+                self.exec(&transition)
+            })
+        });
+        self.report(result)
+    }
+
+    /// Play a game command directly, e.g. for the tile under the keyboard cursor.
+    pub fn play(&mut self, cmd: GameCommand) -> Result<(), GameError> {
+        self.command(Command::Game(cmd))
+    }
+
+    pub fn pause(&mut self) -> Result<(), GameError> {
+        self.command(Command::System(SystemCommand::Pause))
+    }
+
+    fn command(&mut self, c: Command) -> Result<(), GameError> {
+        let result = self
+            .transition(c)
+            .and_then(|transition| self.exec(&transition));
+        self.report(result)
+    }
+
+    // keep the outcome of the last player command around for the UI to show
+    fn report(&mut self, result: Result<(), GameError>) -> Result<(), GameError> {
+        self.error = result.clone().err().unwrap_or_default();
+        result
+    }
+
+    /// Perform a bound action on tile `x, y`, input specific actions are left to the caller.
+    pub fn perform(&mut self, action: Action, x: usize, y: usize) -> Result<(), GameError> {
+        match action {
+            Action::CursorUp => self.move_cursor(-1, 0),
+            Action::CursorDown => self.move_cursor(1, 0),
+            Action::CursorLeft => self.move_cursor(0, -1),
+            Action::CursorRight => self.move_cursor(0, 1),
+            Action::Pause => return self.pause(),
+            Action::CommandLine => {}
+            action => {
+                if let Some(cmd) = action.game_command(x, y) {
+                    return self.play(cmd);
+                }
+            }
         }
+        Ok(())
     }
 
-    pub fn parse_command(&self, cmd: &str) -> Result<Transition, GameError> {
-        let cmd = &cmd.to_lowercase();
+    pub fn move_cursor(&mut self, dx: isize, dy: isize) {
+        let (rows, cols) = self.size();
+        if rows == 0 || cols == 0 {
+            return;
+        }
+        let (x, y) = (self.cursor.0 as isize + dx, self.cursor.1 as isize + dy);
+        let (last_row, last_col) = (rows as isize - 1, cols as isize - 1);
+        // the endless view follows the cursor past its edges
+        let past = |n: isize, last: isize| n.min(0) + (n - last).max(0);
+        self.pan(past(x, last_row), past(y, last_col));
+        self.cursor = (x.clamp(0, last_row) as usize, y.clamp(0, last_col) as usize);
+    }
+
+    pub fn parse_command(&self, cmd: &str) -> Result<Command, GameError> {
+        let cmd = &self.settings.active().resolve_alias(&cmd.to_lowercase());
+        if let Some(def) = Definition::parse(cmd)? {
+            return Ok(Command::System(SystemCommand::Define(def)));
+        }
+        let words = cmd.split_whitespace().collect::<Vec<&str>>();
+        let compact = words.concat();
         match cmd.as_ref() {
-            "restart" | "reset" => self.transition(Command::System(SystemCommand::Restart)),
-            "start" => self.transition(Command::System(SystemCommand::Start)),
-            "quit" | "exit" => self.transition(Command::System(SystemCommand::Exit)),
-            v if v.len() == 3 => {
+            "restart" | "reset" => Ok(Command::System(SystemCommand::Restart)),
+            "start" => Ok(Command::System(SystemCommand::Start)),
+            "quit" | "exit" => Ok(Command::System(SystemCommand::Exit)),
+            _ if matches!(words.first(), Some(&("start" | "restart" | "reset"))) => {
+                let rules = self.rules.with_options(&words[1..])?;
+                Ok(Command::System(SystemCommand::NewGame(rules)))
+            }
+            "settings" => Ok(Command::System(SystemCommand::Settings)),
+            "pause" | "resume" => Ok(Command::System(SystemCommand::Pause)),
+            "export" => Ok(Command::System(SystemCommand::Export)),
+            _ if words.first() == Some(&"import") => {
+                let position = Position::parse(&words[1..].join(" "))?;
+                Ok(Command::System(SystemCommand::Import(Box::new(position))))
+            }
+            "daily" => {
+                let rules = Rules {
+                    mode: GameMode::Daily,
+                    ..Rules::default()
+                };
+                Ok(Command::System(SystemCommand::NewGame(rules)))
+            }
+            _ if words.first() == Some(&"puzzle") => {
+                let [n] = words[1..] else {
+                    return Err(GameError::InvalidArgument);
+                };
+                let n = n.parse::<usize>().map_err(|_| GameError::InvalidArgument)?;
+                Puzzle::from_pack(n)?.validate()?;
+                let rules = Rules {
+                    mode: GameMode::Puzzle(n),
+                    ..Rules::default()
+                };
+                Ok(Command::System(SystemCommand::NewGame(rules)))
+            }
+            _ if words.first() == Some(&"pan") => {
+                let (rows, cols) = Self::pan_offset(&words[1..])?;
+                Ok(Command::System(SystemCommand::Pan(rows, cols)))
+            }
+            _ if compact.contains(',') => Ok(Command::Game(GameCommand::from_axial(&compact)?)),
+            _ if compact.chars().count() == 3 => {
                 let mut chars: [char; 3] = [0 as char; 3];
-                let iter = cmd.chars().collect::<Vec<char>>();
+                let iter = compact.chars().collect::<Vec<char>>();
                 chars[0] = iter[0];
                 chars[1] = iter[1];
                 chars[2] = iter[2];
-                self.transition(Command::Game(chars.try_into()?))
+                Ok(Command::Game(chars.try_into()?))
             }
             _ => Err(GameError::UnknownCommand),
         }
     }
 
-    pub fn timer_checkin(&mut self, current: u64) {
-        if current == 0 {
+    // `pan left 4`, by half the view when no count is given
+    fn pan_offset(args: &[&str]) -> Result<(isize, isize), GameError> {
+        let (direction, count) = match args {
+            [direction] => (direction, BOARD_SIZE.0 as isize / 2),
+            [direction, count] => (
+                direction,
+                count
+                    .parse::<isize>()
+                    .map_err(|_| GameError::InvalidArgument)?,
+            ),
+            _ => return Err(GameError::InvalidArgument),
+        };
+        match *direction {
+            "up" => Ok((-count, 0)),
+            "down" => Ok((count, 0)),
+            "left" => Ok((0, -count)),
+            "right" => Ok((0, count)),
+            _ => Err(GameError::InvalidArgument),
+        }
+    }
+
+    pub fn timer_checkin(&mut self) {
+        if matches!(self.timer_state, TimerState::Started(_)) && self.remaining_millis() == 0 {
             self.detonate_all();
         }
     }
 
+    fn time_bonus(&mut self) {
+        if self.rules.mode == GameMode::TimeAttack {
+            self.time_left += TIME_BONUS;
+            self.result.time_bonus += TIME_BONUS;
+        }
+    }
+
+    fn time_penalty(&mut self) {
+        if self.rules.mode == GameMode::Penalty {
+            let penalty = TIME_PENALTY.min(self.remaining_millis());
+            self.time_left -= penalty;
+            self.result.time_penalty += penalty;
+            self.timer_checkin();
+        }
+    }
+
     pub fn current_state(&self) -> &GameState {
         &self.state
     }
 
+    pub fn is_mine(&self, x: usize, y: usize) -> bool {
+        self.mines.contains(self.mines_map.index_of((x, y)))
+    }
+
+    /// Mines hidden under tile `x, y`.
+    pub fn mines_at(&self, x: usize, y: usize) -> usize {
+        self.mines_map[(x, y)].mines as usize
+    }
+
+    // mines the player has accounted for on tile `x, y`, by a flag or by stepping on them
+    fn marked_mines(&self, x: usize, y: usize) -> usize {
+        match self.board_map[(x, y)] {
+            TileState::Flagged(count) => count as usize,
+            TileState::Detonated => self.mines_at(x, y),
+            _ => 0,
+        }
+    }
+
+    pub fn mines_count(&self) -> usize {
+        self.mines_map.iter().map(|cell| cell.mines as usize).sum()
+    }
+
+    pub fn flags_count(&self) -> usize {
+        self.board_map
+            .iter()
+            .map(|tile| match tile {
+                TileState::Flagged(count) => *count as usize,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Mines not yet accounted for by a flag, negative when there are more flags than mines.
+    pub fn mines_left(&self) -> isize {
+        let marked = self
+            .board_map
+            .positions()
+            .map(|(i, j)| self.marked_mines(i, j))
+            .sum::<usize>();
+        self.mines_count() as isize - marked as isize
+    }
+
     fn transition(&self, c: Command) -> Result<Transition, GameError> {
         match self.current_state() {
             GameState::Init => {
@@ -270,166 +898,268 @@ impl GameCommandExecutor {
         self.state = state;
     }
 
-    fn create_board_map(&mut self) {
-        for i in 0..8 {
-            self.board_map.push(Vec::new());
-            for _ in 0..8 {
-                self.board_map[i].push(TileState::Concealed);
-            }
-        }
-    }
+    // `draw` picks a tile index below the given length
+    fn generate_mines_map(
+        &mut self,
+        rows: usize,
+        cols: usize,
+        mines: usize,
+        mut draw: impl FnMut(usize) -> usize,
+    ) {
+        self.mines_map = Grid::new(rows, cols, Cell::default());
+        self.mines = BitSet::new(rows * cols);
 
-    fn generate_mines_map(&mut self) {
-        let mut rng = rand::thread_rng();
-
-        for i in 0..8 {
-            self.mines_map.push(Vec::new());
-            for _ in 0..8 {
-                self.mines_map[i].push(0);
-            }
-        }
-
-        for _ in 0..16 {
+        let mines = mines.min(rows * cols * self.rules.mines_per_tile as usize);
+        for _ in 0..mines {
             loop {
-                let idx: i8 = rng.gen_range(0..64);
-                if self.mines_map[(idx / 8) as usize][(idx % 8) as usize] == THE_BOMB {
+                let idx = draw(rows * cols);
+                let pos = self.mines_map.pos_of(idx);
+                if self.mines_map[pos].mines >= self.rules.mines_per_tile {
                     continue;
                 }
-                self.mines_map[(idx / 8) as usize][(idx % 8) as usize] = THE_BOMB;
+                self.mines_map[pos].mines += 1;
+                self.mines.insert(idx);
                 break;
             }
         }
+        self.count_adjacent();
+    }
 
-        for i in 0..8 {
-            for j in 0..8 {
-                if self.mines_map[i][j] == THE_BOMB {
-                    continue;
-                }
-
-                let mut counter = 0;
-                for neighbour in self.neighbours(i, j).iter() {
-                    if self.mines_map[neighbour.0][neighbour.1] == THE_BOMB {
-                        counter += 1;
-                    }
-                }
-                self.mines_map[i][j] = counter;
+    fn count_adjacent(&mut self) {
+        for pos in self.mines_map.positions() {
+            if self.mines_map[pos].is_mine() {
+                continue;
             }
+            self.mines_map[pos].adjacent = self
+                .neighbours(pos.0, pos.1)
+                .map(|n| self.mines_map[n].mines)
+                .sum();
         }
     }
 
-    fn neighbours(&self, i: usize, j: usize) -> Vec<(usize, usize)> {
-        let neighbours: Vec<(i8, i8)> = vec![
-            (i as i8 - 1, j as i8 - 1),
-            (i as i8 - 1, j as i8),
-            (i as i8 - 1, j as i8 + 1),
-            (i as i8, j as i8 - 1),
-            (i as i8, j as i8 + 1),
-            (i as i8 + 1, j as i8 - 1),
-            (i as i8 + 1, j as i8),
-            (i as i8 + 1, j as i8 + 1),
-        ];
-        neighbours
-            .into_iter()
-            .filter(|(x, y)| *x >= 0 && *x < 8 && *y >= 0 && *y < 8)
-            .map(|(x, y)| (x as usize, y as usize))
-            .collect()
+    /// Board dimensions as `(rows, columns)`.
+    pub fn size(&self) -> (usize, usize) {
+        self.board_map.size()
     }
 
+    // neighbour offsets of the current rules, the kernel's or else the surface's own
+    fn offsets(&self) -> &'static [(isize, isize)] {
+        self.rules
+            .kernel
+            .offsets()
+            .unwrap_or(self.rules.surface.topology().surrounding())
+    }
+
+    /// Tiles around `i, j` as the kernel and surface of the current rules connect them.
+    pub fn neighbours(&self, i: usize, j: usize) -> Neighbours<'static> {
+        self.rules
+            .surface
+            .topology()
+            .neighbours(self.offsets(), (i, j), self.size())
+    }
+
+    fn reveal(&mut self, pos: Pos) {
+        self.board_map[pos] = TileState::Stepped;
+        self.revealed.insert(self.board_map.index_of(pos));
+    }
+
+    // step on every tile reachable from `x, y` through blank ones,
+    // with a stack of its own so a large empty board can't overflow the call stack
     fn open(&mut self, x: usize, y: usize) {
-        if self.mines_map[x][y] > 0 {
-            return;
-        }
-        for neighbour in self.neighbours(x, y) {
-            if self.board_map[neighbour.0][neighbour.1] != TileState::Concealed {
+        let mut blanks = vec![(x, y)];
+        while let Some((x, y)) = blanks.pop() {
+            if !self.mines_map[(x, y)].is_blank() {
                 continue;
             }
+            for neighbour in self.neighbours(x, y) {
+                if !self.board_map[neighbour].is_concealed() {
+                    continue;
+                }
 
-            // if center tile is 0, then no bomb in the neighbours, safe to step
-            self.board_map[neighbour.0][neighbour.1] = TileState::Stepped;
+                // if center tile is 0, then no bomb in the neighbours, safe to step
+                self.reveal(neighbour);
 
-            // if current neighbour is 0, recursively open the surrounding tiles for the neighbour
-            if self.mines_map[neighbour.0][neighbour.1] == 0 {
-                self.open(neighbour.0, neighbour.1);
+                // if current neighbour is 0, open the surrounding tiles for the neighbour too
+                if self.mines_map[neighbour].is_blank() {
+                    blanks.push(neighbour);
+                }
             }
         }
     }
 
     fn detonate_all(&mut self) {
-        self.all_bombs(99, 99, TileState::Detonated);
+        self.all_bombs(None, TileState::Detonated);
     }
 
-    fn all_bombs(&mut self, x: usize, y: usize, state: TileState) {
-        for i in 0..self.mines_map.len() {
-            for j in 0..self.mines_map[i].len() {
-                if x == i && y == j {
-                    continue;
-                }
-                if self.mines_map[i][j] == THE_BOMB {
-                    self.board_map[i][j] = state.clone();
-                }
+    // show every mine as `state`, except the one at `exempt` which already went off
+    fn all_bombs(&mut self, exempt: Option<Pos>, state: TileState) {
+        for pos in self.mines_map.positions() {
+            if exempt == Some(pos) || self.board_map[pos] == TileState::Detonated {
+                continue;
+            }
+            if self.is_mine(pos.0, pos.1) {
+                self.board_map[pos] = state.clone();
             }
         }
-        self.timer_state = TimerState::Reset;
+        self.stop_timer();
         self.transition_into(GameState::Lose);
+        self.record_daily(DailyOutcome::Lost);
     }
 
     fn step(&mut self, x: usize, y: usize) {
-        if self.board_map[x][y] == TileState::Concealed {
-            if self.mines_map[x][y] == THE_BOMB {
-                self.board_map[x][y] = TileState::Detonated;
-                return self.all_bombs(x, y, TileState::Revealed);
+        if self.board_map[(x, y)].is_concealed() {
+            if self.is_mine(x, y) {
+                if self.rules.mode == GameMode::Zen {
+                    self.board_map[(x, y)] = TileState::Flagged(self.mines_map[(x, y)].mines);
+                    return;
+                }
+                self.board_map[(x, y)] = TileState::Detonated;
+                if !self.survive_hit() {
+                    return self.all_bombs(Some((x, y)), TileState::Revealed);
+                }
+            } else {
+                self.reveal((x, y));
+                self.open(x, y);
+                self.time_bonus();
             }
-            self.board_map[x][y] = TileState::Stepped;
-            self.open(x, y);
         }
-        if self.considered_win() {
+        if self.rules.mode != GameMode::Zen && self.considered_win() {
+            self.flag_remaining_mines();
             self.state = GameState::Win;
-            self.timer_state = TimerState::Reset;
+            self.stop_timer();
+            self.record_daily(DailyOutcome::Won(self.elapsed));
         }
     }
 
-    fn neighbours_step(&mut self, x: usize, y: usize) {
-        if self.board_map[x][y] == TileState::Concealed {
-            return;
+    fn record_daily(&mut self, outcome: DailyOutcome) {
+        if self.rules.mode == GameMode::Daily {
+            self.daily.finish(outcome);
         }
-        let neighbours = self.neighbours(x, y);
-        let mut i: usize = 0;
-        while self.state == GameState::DrawBoard {
-            if i == neighbours.len() {
-                break;
-            }
+    }
 
-            let n = neighbours[i];
+    // deal a fresh board in zen once the current one is cleared
+    fn next_board(&mut self) {
+        self.generate(BOARD_SIZE.0, BOARD_SIZE.1, MINES);
+        self.result.cleared += 1;
+    }
 
-            i += 1;
+    // take a life in lives mode, false once there is none left to take
+    fn survive_hit(&mut self) -> bool {
+        let Some(lives) = self.lives else {
+            return false;
+        };
+        self.lives = Some(lives - 1);
+        self.result.hits += 1;
+        lives > 1
+    }
 
-            if self.board_map[n.0][n.1] != TileState::Concealed {
+    // flag every mine left, with the right count even where the player's was off
+    fn flag_remaining_mines(&mut self) {
+        for pos in self.mines_map.positions() {
+            if self.is_mine(pos.0, pos.1)
+                && (self.board_map[pos].is_concealed()
+                    || matches!(self.board_map[pos], TileState::Flagged(_)))
+            {
+                self.board_map[pos] = TileState::Flagged(self.mines_map[pos].mines);
+            }
+        }
+    }
+
+    // flag the concealed neighbours of every number which can only be mines,
+    // i.e. when they can only hold all of the mines not yet flagged around it
+    // by each holding as many as a tile may
+    fn auto_flag(&mut self) {
+        for (i, j) in self.board_map.positions() {
+            if self.board_map[(i, j)] != TileState::Stepped || self.mines_map[(i, j)].adjacent == 0
+            {
+                continue;
+            }
+
+            let flags = self
+                .neighbours(i, j)
+                .map(|n| self.marked_mines(n.0, n.1))
+                .sum::<usize>();
+            let concealed = self
+                .neighbours(i, j)
+                .filter(|n| self.board_map[*n].is_concealed())
+                .count();
+            let per_tile = self.rules.mines_per_tile;
+            if concealed == 0
+                || concealed * per_tile as usize + flags != self.mines_map[(i, j)].adjacent as usize
+            {
                 continue;
             }
 
-            self.step(n.0, n.1);
+            for n in self.neighbours(i, j) {
+                if self.board_map[n].is_concealed() {
+                    self.board_map[n] = TileState::Flagged(per_tile);
+                }
+            }
+            self.result.assisted_flags += concealed;
         }
     }
 
+    fn neighbours_step(&mut self, x: usize, y: usize) -> Result<(), GameError> {
+        // only numbers chord, anything else would tell what the tile hides
+        if self.board_map[(x, y)] != TileState::Stepped {
+            return Ok(());
+        }
+
+        // only chord once the number is satisfied with flags, unless asked to be aggressive
+        let flags = self
+            .neighbours(x, y)
+            .map(|n| self.marked_mines(n.0, n.1))
+            .sum::<usize>();
+        if !self.settings.active().aggressive_chord
+            && flags != self.mines_map[(x, y)].adjacent as usize
+        {
+            self.time_penalty();
+            return Err(GameError::ChordRefused);
+        }
+
+        for n in self.neighbours(x, y) {
+            if self.state != GameState::DrawBoard {
+                break;
+            }
+            if self.board_map[n].is_concealed() {
+                self.step(n.0, n.1);
+            }
+        }
+        Ok(())
+    }
+
     fn flag(&mut self, x: usize, y: usize) {
-        if self.board_map[x][y] == TileState::Concealed {
-            self.board_map[x][y] = TileState::Flagged;
+        if self.board_map[(x, y)].is_concealed() {
+            self.board_map[(x, y)] = TileState::Flagged(1);
+            self.flagged(x, y);
+        }
+    }
+
+    // wrong flag, more mines than the tile holds
+    fn flagged(&mut self, x: usize, y: usize) {
+        if let TileState::Flagged(count) = self.board_map[(x, y)] {
+            if count as usize > self.mines_at(x, y) {
+                self.time_penalty();
+            }
         }
     }
 
     fn unflag(&mut self, x: usize, y: usize) {
-        if self.board_map[x][y] == TileState::Flagged {
-            self.board_map[x][y] = TileState::Concealed;
+        if let TileState::Flagged(_) = self.board_map[(x, y)] {
+            self.board_map[(x, y)] = TileState::Concealed;
         }
     }
 
     fn toggle_flag(&mut self, x: usize, y: usize) {
-        let tile = self.board_map[x][y].clone();
-        if tile == TileState::Flagged {
-            self.board_map[x][y] = TileState::Concealed;
-        } else if tile == TileState::Concealed {
-            self.board_map[x][y] = TileState::Flagged;
-        }
+        self.board_map[(x, y)] = self.board_map[(x, y)].toggled(
+            self.rules.mines_per_tile,
+            self.settings.active().question_marks,
+        );
+        self.flagged(x, y);
+    }
+
+    fn question(&mut self, x: usize, y: usize) {
+        self.board_map[(x, y)] = self.board_map[(x, y)].questioned();
     }
 
     // winning condition
@@ -437,16 +1167,268 @@ impl GameCommandExecutor {
         self.all_closed_or_flagged_tiles_are_mines() && self.still_playing()
     }
 
+    // flag counts aren't checked, every safe tile stepped is enough
     fn all_closed_or_flagged_tiles_are_mines(&self) -> bool {
-        self.board_map
-            .iter()
-            .enumerate()
-            .flat_map(|(i, row)| row.iter().enumerate().map(move |(j, cell)| ((i, j), cell)))
-            .filter(|(_, cell)| **cell == TileState::Concealed || **cell == TileState::Flagged)
-            .all(|((i, j), _)| self.mines_map[i][j] == THE_BOMB)
+        self.revealed.count() + self.mines.count() == self.board_map.len()
     }
 
     fn still_playing(&self) -> bool {
         self.state != GameState::Lose
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Macro;
+
+    // a board in play with the given layout, `*` for a mine or the count of those stacked
+    fn board(layout: &[&str]) -> GameCommandExecutor {
+        let (rows, cols) = (layout.len(), layout[0].len());
+        let mut mines_map = Grid::new(rows, cols, Cell::default());
+        for (i, row) in layout.iter().enumerate() {
+            for (j, tile) in row.chars().enumerate() {
+                mines_map[(i, j)].mines = match tile {
+                    '*' => 1,
+                    _ => tile.to_digit(10).unwrap_or(0) as u8,
+                };
+            }
+        }
+        let mut gcx = GameCommandExecutor::new(Settings::default());
+        gcx.load(mines_map, Grid::new(rows, cols, TileState::Concealed));
+        gcx.count_adjacent();
+        gcx.transition_into(GameState::DrawBoard);
+        gcx
+    }
+
+    #[test]
+    fn chord_steps_once_the_flags_match() {
+        let mut gcx = board(&["*..", "...", "..."]);
+        gcx.step(0, 1);
+        gcx.flag(0, 0);
+        assert_eq!(gcx.neighbours_step(0, 1), Ok(()));
+        assert_eq!(gcx.board_map[(1, 1)], TileState::Stepped);
+    }
+
+    #[test]
+    fn chord_is_refused_and_penalised_when_the_flags_dont_match() {
+        let mut gcx = board(&["*..", "...", "..."]);
+        gcx.rules.mode = GameMode::Penalty;
+        gcx.step(0, 1);
+        assert_eq!(gcx.neighbours_step(0, 1), Err(GameError::ChordRefused));
+        assert_eq!(gcx.board_map[(1, 1)], TileState::Concealed);
+        assert_eq!(gcx.result.time_penalty, TIME_PENALTY);
+    }
+
+    #[test]
+    fn chord_ignores_a_wrongly_flagged_tile() {
+        let mut gcx = board(&["*..", "...", "..."]);
+        gcx.rules.mode = GameMode::Penalty;
+        gcx.flag(0, 1);
+        let penalty = gcx.result.time_penalty;
+        assert_eq!(gcx.neighbours_step(0, 1), Ok(()));
+        assert_eq!(gcx.board_map[(1, 1)], TileState::Concealed);
+        assert_eq!(gcx.result.time_penalty, penalty);
+    }
+
+    #[test]
+    fn chord_on_a_flagged_mine_gives_nothing_away() {
+        let mut gcx = board(&["*..", "...", "..*"]);
+        gcx.rules.mode = GameMode::Penalty;
+        gcx.flag(0, 0);
+        let penalty = gcx.result.time_penalty;
+        // the count a mine keeps matches no flags around it
+        assert_eq!(gcx.neighbours_step(0, 0), Ok(()));
+        assert_eq!(gcx.board_map[(0, 1)], TileState::Concealed);
+        assert_eq!(gcx.result.time_penalty, penalty);
+    }
+
+    #[test]
+    fn macro_with_a_bad_command_plays_none_of_it() {
+        let mut gcx = board(&["*..", "...", "..."]);
+        gcx.settings.active_mut().macros.insert(
+            "oops".to_string(),
+            Macro {
+                params: Vec::new(),
+                body: "sb2 sc3 nonsense".to_string(),
+            },
+        );
+        assert_eq!(gcx.run("oops"), Err(GameError::UnknownCommand));
+        assert_eq!(gcx.board_map[(1, 1)], TileState::Concealed);
+        assert_eq!(gcx.error, GameError::UnknownCommand);
+
+        assert_eq!(gcx.run("sb2"), Ok(()));
+        assert_eq!(gcx.board_map[(1, 1)], TileState::Stepped);
+    }
+
+    #[test]
+    fn daily_board_is_fixed_by_the_date_and_preset() {
+        let mut gcx = GameCommandExecutor::new(Settings::default());
+        gcx.rules.mode = GameMode::Daily;
+        gcx.daily.date = "2026-10-19".to_string();
+        gcx.generate(4, 4, 4);
+        let mines = gcx
+            .mines_map
+            .positions()
+            .filter(|pos| gcx.mines_map[*pos].is_mine())
+            .collect::<Vec<_>>();
+        assert_eq!(mines, [(0, 2), (1, 3), (3, 1), (3, 2)]);
+    }
+
+    #[test]
+    fn aggressive_chord_steps_regardless_of_flags() {
+        let mut gcx = board(&["*.*", "...", "..."]);
+        gcx.settings.active_mut().aggressive_chord = true;
+        gcx.step(1, 1);
+        assert_eq!(gcx.neighbours_step(1, 1), Ok(()));
+        assert_eq!(gcx.state, GameState::Lose);
+    }
+
+    #[test]
+    fn cells_tell_mines_from_numbers() {
+        let gcx = board(&["*..", "...", "..*"]);
+        assert!(gcx.mines_map[(0, 0)].is_mine());
+        assert!(!gcx.mines_map[(0, 0)].is_blank());
+        assert_eq!(
+            gcx.mines_map[(1, 1)],
+            Cell {
+                mines: 0,
+                adjacent: 2
+            }
+        );
+        assert!(gcx.mines_map[(0, 2)].is_blank());
+        assert!(Cell::default().is_blank());
+    }
+
+    #[test]
+    fn all_bombs_shows_every_mine_and_nothing_else() {
+        let mut gcx = board(&["*..", "...", "..*"]);
+        gcx.all_bombs(None, TileState::Detonated);
+        assert_eq!(gcx.board_map[(0, 0)], TileState::Detonated);
+        assert_eq!(gcx.board_map[(2, 2)], TileState::Detonated);
+        let shown = gcx
+            .board_map
+            .iter()
+            .filter(|tile| !tile.is_concealed())
+            .count();
+        assert_eq!(shown, 2);
+        assert_eq!(gcx.state, GameState::Lose);
+    }
+
+    #[test]
+    fn tiles_past_a_smaller_board_are_refused() {
+        let mut gcx = GameCommandExecutor::new(Settings::default());
+        assert_eq!(gcx.run("puzzle 1"), Ok(()));
+        assert_eq!(gcx.size(), (6, 6));
+        let board_map = gcx.board_map.clone();
+        for cmd in ["sh8", "sa7", "fg1", "s7,1", "s1,7"] {
+            assert_eq!(gcx.run(cmd), Err(GameError::InvalidArgument), "{}", cmd);
+        }
+        assert_eq!(gcx.board_map, board_map);
+        assert_eq!(gcx.run("ff6"), Ok(()));
+        assert_eq!(gcx.board_map[(5, 5)], TileState::Flagged(1));
+    }
+
+    #[test]
+    fn a_missing_puzzle_keeps_the_current_game() {
+        let mut gcx = GameCommandExecutor::new(Settings::default());
+        assert_eq!(gcx.run("puzzle 1"), Ok(()));
+        let board_map = gcx.board_map.clone();
+        let rules = Rules {
+            mode: GameMode::Puzzle(99),
+            ..Rules::default()
+        };
+        let cmd = Transition::DrawBoard(Command::System(SystemCommand::NewGame(rules)));
+        assert_eq!(gcx.exec(&cmd), Err(GameError::InvalidArgument));
+        assert_eq!(gcx.rules.mode, GameMode::Puzzle(1));
+        assert_eq!(gcx.board_map, board_map);
+    }
+
+    #[test]
+    fn flags_cycle_up_to_the_mines_a_tile_may_hold() {
+        let mut gcx = board(&["3."]);
+        gcx.rules.mines_per_tile = 3;
+        for count in 1..=3 {
+            gcx.toggle_flag(0, 0);
+            assert_eq!(gcx.board_map[(0, 0)], TileState::Flagged(count));
+        }
+        gcx.toggle_flag(0, 0);
+        assert_eq!(gcx.board_map[(0, 0)], TileState::Concealed);
+    }
+
+    #[test]
+    fn more_flags_than_mines_are_penalised() {
+        let mut gcx = board(&["2."]);
+        gcx.rules.mode = GameMode::Penalty;
+        gcx.rules.mines_per_tile = 3;
+        gcx.toggle_flag(0, 0);
+        gcx.toggle_flag(0, 0);
+        assert_eq!(gcx.result.time_penalty, 0);
+        gcx.toggle_flag(0, 0);
+        assert_eq!(gcx.result.time_penalty, TIME_PENALTY);
+        gcx.flag(0, 1);
+        assert_eq!(gcx.result.time_penalty, TIME_PENALTY * 2);
+    }
+
+    #[test]
+    fn chord_counts_every_flag_on_a_tile() {
+        let mut gcx = board(&["2..", "...", "..."]);
+        gcx.rules.mines_per_tile = 2;
+        gcx.step(0, 1);
+        gcx.toggle_flag(0, 0);
+        assert_eq!(gcx.neighbours_step(0, 1), Err(GameError::ChordRefused));
+        gcx.toggle_flag(0, 0);
+        assert_eq!(gcx.neighbours_step(0, 1), Ok(()));
+        assert_eq!(gcx.board_map[(1, 1)], TileState::Stepped);
+    }
+
+    #[test]
+    fn stacked_mines_are_flagged_with_their_count_on_a_win() {
+        let mut gcx = board(&["2.", ".*"]);
+        gcx.rules.mines_per_tile = 2;
+        gcx.flag(0, 0);
+        gcx.step(0, 1);
+        assert_eq!(gcx.state, GameState::DrawBoard);
+        gcx.step(1, 0);
+        assert_eq!(gcx.state, GameState::Win);
+        assert_eq!(gcx.board_map[(0, 0)], TileState::Flagged(2));
+        assert_eq!(gcx.board_map[(1, 1)], TileState::Flagged(1));
+    }
+
+    #[test]
+    fn auto_flag_only_fills_in_full_stacks() {
+        let mut gcx = board(&["2...*."]);
+        gcx.rules.mines_per_tile = 2;
+        gcx.settings.active_mut().auto_flag = true;
+        assert_eq!(gcx.exec_game_command(&GameCommand::Step(0, 2)), Ok(()));
+        assert_eq!(gcx.board_map[(0, 0)], TileState::Flagged(2));
+        // a single mine next to the 1 could as well be half of a stack
+        assert_eq!(gcx.board_map[(0, 4)], TileState::Concealed);
+    }
+
+    #[test]
+    fn a_life_is_lost_for_each_mine_until_none_are_left() {
+        let mut gcx = board(&["*.*", "..."]);
+        gcx.lives = Some(2);
+        gcx.step(0, 0);
+        assert_eq!(gcx.board_map[(0, 0)], TileState::Detonated);
+        assert_eq!(gcx.lives, Some(1));
+        assert_eq!(gcx.state, GameState::DrawBoard);
+        gcx.step(0, 2);
+        assert_eq!(gcx.lives, Some(0));
+        assert_eq!(gcx.result.hits, 2);
+        assert_eq!(gcx.state, GameState::Lose);
+    }
+
+    #[test]
+    fn zen_deals_a_new_board_once_one_is_cleared() {
+        let mut gcx = board(&["*."]);
+        gcx.rules.mode = GameMode::Zen;
+        assert_eq!(gcx.exec_game_command(&GameCommand::Step(0, 1)), Ok(()));
+        assert_eq!(gcx.result.cleared, 1);
+        assert_eq!(gcx.size(), BOARD_SIZE);
+        assert_eq!(gcx.mines_count(), MINES);
+        assert!(gcx.board_map.iter().all(|tile| tile.is_concealed()));
+        assert_eq!(gcx.state, GameState::DrawBoard);
+    }
+}
@@ -5,9 +5,23 @@ extern "C" {
     #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"])]
     pub async fn invoke(cmd: &str, args: JsValue) -> JsValue;
 
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "event"])]
+    pub fn listen(event: &str, handler: &Closure<dyn Fn(JsValue)>) -> js_sys::Promise;
+
     #[wasm_bindgen(js_namespace = ["window", "console"])]
     pub fn log(stuff: JsValue);
 
+    /// Throws without a clipboard, e.g. outside a secure context.
+    #[wasm_bindgen(catch, js_namespace = ["window", "navigator", "clipboard"], js_name = writeText)]
+    pub fn write_text(text: &str) -> Result<js_sys::Promise, JsValue>;
+
     #[wasm_bindgen(js_namespace = ["window", "Date"])]
     pub fn now() -> f64;
 }
+
+/// Whether the page runs inside Tauri, as opposed to a plain browser tab.
+pub fn has_tauri() -> bool {
+    web_sys::window()
+        .map(|window| js_sys::Reflect::has(&window, &"__TAURI__".into()).unwrap_or_default())
+        .unwrap_or_default()
+}
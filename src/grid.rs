@@ -0,0 +1,145 @@
+use std::ops::{Index, IndexMut};
+
+/// Position of a tile on the board, as `(row, column)`.
+pub type Pos = (usize, usize);
+
+/// Tiles of a board, stored row after row in a single `Vec`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    rows: usize,
+    cols: usize,
+    tiles: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(rows: usize, cols: usize, tile: T) -> Self {
+        Self {
+            rows,
+            cols,
+            tiles: vec![tile; rows * cols],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    /// Dimensions as `(rows, columns)`.
+    pub fn size(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Flat index of the tile at `pos`, the same for every grid of this size.
+    pub fn index_of(&self, (i, j): Pos) -> usize {
+        debug_assert!(i < self.rows && j < self.cols);
+        i * self.cols + j
+    }
+
+    pub fn pos_of(&self, index: usize) -> Pos {
+        (index / self.cols, index % self.cols)
+    }
+
+    /// Every position on the grid, row after row.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let cols = self.cols;
+        (0..self.tiles.len()).map(move |n| (n / cols, n % cols))
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // an empty grid has no columns, but `chunks` won't take 0
+        self.tiles.chunks(self.cols.max(1))
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.tiles.iter()
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        &self.tiles[self.index_of(pos)]
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        let index = self.index_of(pos);
+        &mut self.tiles[index]
+    }
+}
+
+/// Set of tiles by their flat index, one bit each.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct BitSet {
+    words: Vec<u64>,
+    count: usize,
+}
+
+impl BitSet {
+    pub fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)],
+            count: 0,
+        }
+    }
+
+    pub fn contains(&self, n: usize) -> bool {
+        self.words
+            .get(n / 64)
+            .is_some_and(|word| word >> (n % 64) & 1 == 1)
+    }
+
+    /// Add `n` to the set, false when it was already there.
+    pub fn insert(&mut self, n: usize) -> bool {
+        let (word, bit) = (n / 64, 1 << (n % 64));
+        if self.words[word] & bit != 0 {
+            return false;
+        }
+        self.words[word] |= bit;
+        self.count += 1;
+        true
+    }
+
+    /// Number of tiles in the set, kept as they are added.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_and_position_agree() {
+        let grid = Grid::new(3, 5, 0);
+        for (index, pos) in grid.positions().enumerate() {
+            assert_eq!(grid.index_of(pos), index);
+            assert_eq!(grid.pos_of(index), pos);
+        }
+        assert_eq!(grid.index_of((2, 0)), 10);
+        assert_eq!(grid.pos_of(14), (2, 4));
+        assert_eq!(grid.rows().count(), 3);
+    }
+
+    #[test]
+    fn bitset_counts_each_tile_once() {
+        let mut set = BitSet::new(130);
+        assert!(set.insert(0));
+        assert!(set.insert(63));
+        assert!(set.insert(64));
+        assert!(set.insert(129));
+        assert!(!set.insert(64));
+        assert_eq!(set.count(), 4);
+        assert!(set.contains(63) && set.contains(64) && set.contains(129));
+        assert!(!set.contains(1) && !set.contains(65) && !set.contains(500));
+    }
+}
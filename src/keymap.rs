@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+
+use crate::exec::GameCommand;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Input {
+    LeftClick,
+    RightClick,
+    MiddleClick,
+    DoubleClick,
+    LeftRightClick,
+    Key(String),
+}
+
+impl Input {
+    pub const MOUSE: [Input; 5] = [
+        Input::LeftClick,
+        Input::RightClick,
+        Input::MiddleClick,
+        Input::DoubleClick,
+        Input::LeftRightClick,
+    ];
+
+    /// Map `MouseEvent::button()` of a click into its input.
+    pub fn from_button(button: i16) -> Self {
+        match button {
+            0 => Input::LeftClick,
+            1 => Input::MiddleClick,
+            _ => Input::RightClick,
+        }
+    }
+
+    /// Keys are matched case-insensitively, so `F` and `f` are the same binding.
+    pub fn key(key: &str) -> Self {
+        Input::Key(key.to_lowercase())
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Input::LeftClick => "left click".to_string(),
+            Input::RightClick => "right click".to_string(),
+            Input::MiddleClick => "middle click".to_string(),
+            Input::DoubleClick => "double click".to_string(),
+            Input::LeftRightClick => "left+right click".to_string(),
+            Input::Key(key) if key == " " => "space".to_string(),
+            Input::Key(key) => key.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Step,
+    NeighboursStep,
+    Flag,
+    Unflag,
+    Toggle,
+    Question,
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    Pause,
+    CommandLine,
+}
+
+impl Action {
+    pub const ALL: [Action; 12] = [
+        Action::Step,
+        Action::NeighboursStep,
+        Action::Flag,
+        Action::Unflag,
+        Action::Toggle,
+        Action::Question,
+        Action::CursorUp,
+        Action::CursorDown,
+        Action::CursorLeft,
+        Action::CursorRight,
+        Action::Pause,
+        Action::CommandLine,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Step => "step",
+            Action::NeighboursStep => "step neighbours",
+            Action::Flag => "flag",
+            Action::Unflag => "unflag",
+            Action::Toggle => "toggle flag",
+            Action::Question => "question mark",
+            Action::CursorUp => "cursor up",
+            Action::CursorDown => "cursor down",
+            Action::CursorLeft => "cursor left",
+            Action::CursorRight => "cursor right",
+            Action::Pause => "pause",
+            Action::CommandLine => "type command",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.label() == label)
+    }
+
+    /// The game command this action stands for when applied to tile `x, y`,
+    /// `None` for actions which don't touch the board.
+    pub fn game_command(&self, x: usize, y: usize) -> Option<GameCommand> {
+        match self {
+            Action::Step => Some(GameCommand::Step(x, y)),
+            Action::NeighboursStep => Some(GameCommand::NeighboursStep(x, y)),
+            Action::Flag => Some(GameCommand::Flag(x, y)),
+            Action::Unflag => Some(GameCommand::Unflag(x, y)),
+            Action::Toggle => Some(GameCommand::Toggle(x, y)),
+            Action::Question => Some(GameCommand::Question(x, y)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keymap {
+    pub bindings: Vec<(Input, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let keys = [
+            ("arrowup", Action::CursorUp),
+            ("k", Action::CursorUp),
+            ("arrowdown", Action::CursorDown),
+            ("j", Action::CursorDown),
+            ("arrowleft", Action::CursorLeft),
+            ("h", Action::CursorLeft),
+            ("arrowright", Action::CursorRight),
+            ("l", Action::CursorRight),
+            (" ", Action::Step),
+            ("f", Action::Toggle),
+            ("d", Action::NeighboursStep),
+            ("q", Action::Question),
+            ("escape", Action::Pause),
+            ("tab", Action::CommandLine),
+            ("enter", Action::CommandLine),
+            (":", Action::CommandLine),
+        ];
+
+        let mut bindings = vec![
+            (Input::LeftClick, Action::Step),
+            (Input::RightClick, Action::Toggle),
+            (Input::MiddleClick, Action::NeighboursStep),
+            (Input::DoubleClick, Action::NeighboursStep),
+            (Input::LeftRightClick, Action::NeighboursStep),
+        ];
+        bindings.extend(keys.map(|(key, action)| (Input::key(key), action)));
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    pub fn action(&self, input: &Input) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| bound == input)
+            .map(|(_, action)| *action)
+    }
+
+    /// Bind `input` to `action`, or remove the binding with `None`.
+    pub fn bind(&mut self, input: Input, action: Option<Action>) {
+        match (
+            self.bindings.iter_mut().find(|(bound, _)| *bound == input),
+            action,
+        ) {
+            (Some(binding), Some(action)) => binding.1 = action,
+            (None, Some(action)) => self.bindings.push((input, action)),
+            (_, None) => self.bindings.retain(|(bound, _)| *bound != input),
+        }
+    }
+
+    /// Flag mode swaps the left and right click, for laptops without a right button.
+    pub fn flag_mode(&self) -> bool {
+        self.action(&Input::LeftClick) == Some(Action::Toggle)
+    }
+
+    pub fn set_flag_mode(&mut self, enabled: bool) {
+        let (left, right) = if enabled {
+            (Action::Toggle, Action::Step)
+        } else {
+            (Action::Step, Action::Toggle)
+        };
+        self.bind(Input::LeftClick, Some(left));
+        self.bind(Input::RightClick, Some(right));
+    }
+}
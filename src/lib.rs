@@ -1,10 +1,18 @@
-use std::ops::Div;
-
 pub mod components;
+pub mod daily;
+pub mod endless;
 pub mod errors;
 pub mod exec;
 pub mod external_binding;
+pub mod grid;
+pub mod keymap;
+pub mod position;
+pub mod puzzle;
+pub mod result;
+pub mod rules;
+pub mod settings;
+pub mod topology;
 
-pub(crate) fn current_seconds() -> u64 {
-    external_binding::now().div(1000f64).floor() as u64
+pub(crate) fn current_millis() -> u64 {
+    external_binding::now().floor() as u64
 }
@@ -0,0 +1,254 @@
+use std::fmt;
+
+use crate::{
+    errors::GameError,
+    exec::{Cell, TileState, BOARD_SIZE},
+    grid::Grid,
+    rules::{Rules, MAX_MINES_PER_TILE},
+    topology::{Kernel, Surface},
+};
+
+/// A board as text, to be passed around and played again exactly as it was.
+///
+/// Written on a single line, so it survives being pasted into the command line,
+/// e.g. `3x4 torus multi 2 .*../..../2... -o--/-of-/----`. The header gives the rows and
+/// the columns, followed by the surface, the kernel and the mines a tile may hold
+/// when they aren't the classic ones.
+/// Rows are separated by `/` or whitespace, first those of the layout, each tile being
+/// `.` when safe, `*` for a mine, or the count of mines it holds. The rows of the overlay
+/// may follow, with `-` for a concealed tile, `o` for a stepped one, `f` or the count of
+/// flags for a flagged one, `?` when questioned, `x` for a detonated mine and `*` for
+/// a revealed one. Without them the whole board is concealed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub rules: Rules,
+    /// Mines of the board, the adjacent counts are left for the rules to fill in.
+    pub mines_map: Grid<Cell>,
+    pub board_map: Grid<TileState>,
+}
+
+impl Position {
+    pub fn parse(text: &str) -> Result<Self, GameError> {
+        let mut words = text.split_whitespace();
+        let (rows, cols) = words
+            .next()
+            .and_then(|header| header.split_once('x'))
+            .and_then(|(rows, cols)| Some((rows.parse().ok()?, cols.parse().ok()?)))
+            // no larger than typed commands can reach
+            .filter(|&(rows, cols)| {
+                (1..=BOARD_SIZE.0).contains(&rows) && (1..=BOARD_SIZE.1).contains(&cols)
+            })
+            .ok_or(GameError::InvalidBoard)?;
+
+        let mut options = Vec::new();
+        let mut lines = Vec::new();
+        while let Some(word) = words.next() {
+            if word == "multi" {
+                // its count would pass for a row
+                options.push(word);
+                options.extend(words.next());
+            } else if word
+                .chars()
+                .all(|c| c.is_ascii_digit() || "./*-ofx?".contains(c))
+            {
+                lines.push(word);
+            } else {
+                options.push(word);
+            }
+        }
+        let options = Rules::default()
+            .with_options(&options)
+            .map_err(|_| GameError::InvalidBoard)?;
+        let lines = lines
+            .iter()
+            .flat_map(|line| line.split('/'))
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<&str>>();
+        let (layout, overlay) = match lines.len() {
+            n if n == rows => (&lines[..], None),
+            n if n == rows * 2 => (&lines[..rows], Some(&lines[rows..])),
+            _ => return Err(GameError::InvalidBoard),
+        };
+        if lines.iter().any(|line| line.chars().count() != cols) {
+            return Err(GameError::InvalidBoard);
+        }
+
+        let mut mines_map = Grid::new(rows, cols, Cell::default());
+        let mut board_map = Grid::new(rows, cols, TileState::Concealed);
+        for (i, line) in layout.iter().enumerate() {
+            for (j, tile) in line.chars().enumerate() {
+                mines_map[(i, j)].mines = match tile {
+                    '.' => 0,
+                    '*' => 1,
+                    _ => count(tile).ok_or(GameError::InvalidBoard)?,
+                };
+            }
+        }
+        for (i, line) in overlay.unwrap_or_default().iter().enumerate() {
+            for (j, tile) in line.chars().enumerate() {
+                let mine = mines_map[(i, j)].is_mine();
+                board_map[(i, j)] = match tile {
+                    '-' => TileState::Concealed,
+                    'o' if !mine => TileState::Stepped,
+                    'f' => TileState::Flagged(1),
+                    '?' => TileState::Questioned,
+                    'x' if mine => TileState::Detonated,
+                    '*' if mine => TileState::Revealed,
+                    _ => TileState::Flagged(count(tile).ok_or(GameError::InvalidBoard)?),
+                };
+            }
+        }
+
+        let most = mines_map
+            .iter()
+            .map(|cell| cell.mines)
+            .chain(board_map.iter().map(|tile| match tile {
+                TileState::Flagged(flags) => *flags,
+                _ => 0,
+            }))
+            .max()
+            .unwrap_or_default();
+        if most > options.mines_per_tile {
+            return Err(GameError::InvalidBoard);
+        }
+        Ok(Self {
+            rules: Rules {
+                surface: options.surface,
+                kernel: options.kernel,
+                mines_per_tile: options.mines_per_tile,
+                ..Rules::default()
+            },
+            mines_map,
+            board_map,
+        })
+    }
+}
+
+// tiles holding more than one mine or flag are written with their count
+fn count(tile: char) -> Option<u8> {
+    tile.to_digit(10)
+        .map(|n| n as u8)
+        .filter(|n| (2..=MAX_MINES_PER_TILE).contains(n))
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (rows, cols) = self.mines_map.size();
+        write!(f, "{}x{}", rows, cols)?;
+        if self.rules.surface != Surface::default() {
+            write!(f, " {}", self.rules.surface.label())?;
+        }
+        if self.rules.kernel != Kernel::default() {
+            write!(f, " {}", self.rules.kernel.label())?;
+        }
+        if self.rules.mines_per_tile != 1 {
+            write!(f, " multi {}", self.rules.mines_per_tile)?;
+        }
+
+        let layout = self.mines_map.rows().map(|row| {
+            row.iter()
+                .map(|cell| match cell.mines {
+                    0 => '.',
+                    1 => '*',
+                    n => char::from(b'0' + n),
+                })
+                .collect::<String>()
+        });
+        write!(f, " {}", layout.collect::<Vec<_>>().join("/"))?;
+
+        if self
+            .board_map
+            .iter()
+            .all(|tile| *tile == TileState::Concealed)
+        {
+            return Ok(());
+        }
+        let overlay = self.board_map.rows().map(|row| {
+            row.iter()
+                .map(|tile| match tile {
+                    TileState::Concealed => '-',
+                    TileState::Stepped => 'o',
+                    TileState::Flagged(1) => 'f',
+                    TileState::Flagged(n) => char::from(b'0' + n),
+                    TileState::Questioned => '?',
+                    TileState::Detonated => 'x',
+                    TileState::Revealed => '*',
+                })
+                .collect::<String>()
+        });
+        write!(f, " {}", overlay.collect::<Vec<_>>().join("/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(text: &str) {
+        let position = Position::parse(text).unwrap();
+        assert_eq!(position.to_string(), text);
+        assert_eq!(Position::parse(&position.to_string()), Ok(position));
+    }
+
+    #[test]
+    fn round_trips() {
+        round_trip("2x3 .*./...");
+        round_trip("2x3 .*./... o-f/?--");
+        round_trip("2x3 multi 3 .3./*.. o*2/x-?");
+        round_trip("3x2 torus knight .*/../*.");
+        round_trip("2x2 hex multi 2 ../..");
+    }
+
+    #[test]
+    fn keeps_the_mines_a_tile_may_hold_without_any_stacked() {
+        let position = Position::parse("2x2 multi 3 *./..").unwrap();
+        assert_eq!(position.rules.mines_per_tile, 3);
+    }
+
+    #[test]
+    fn reads_the_header_options() {
+        let position = Position::parse("2x2 cylinder cross *./..").unwrap();
+        assert_eq!(position.rules.surface, Surface::Cylinder);
+        assert_eq!(position.rules.kernel, Kernel::Cross);
+        assert_eq!(position.mines_map.size(), (2, 2));
+        assert!(position.mines_map[(0, 0)].is_mine());
+        assert!(Position::parse("2x2 sideways */..").is_err());
+        assert!(Position::parse("2x2 *. ..").is_ok());
+    }
+
+    #[test]
+    fn rejects_overlays_at_odds_with_the_layout() {
+        assert_eq!(Position::parse("1x2 *. o-"), Err(GameError::InvalidBoard));
+        assert_eq!(Position::parse("1x2 *. -x"), Err(GameError::InvalidBoard));
+        assert_eq!(Position::parse("1x2 *. -*"), Err(GameError::InvalidBoard));
+        assert_eq!(Position::parse("1x2 2. --"), Err(GameError::InvalidBoard));
+    }
+
+    #[test]
+    fn rejects_a_wrong_shape() {
+        assert_eq!(
+            Position::parse("2x2 ../../.."),
+            Err(GameError::InvalidBoard)
+        );
+        assert_eq!(Position::parse("2x2 .."), Err(GameError::InvalidBoard));
+        assert_eq!(Position::parse("2x2 .../.."), Err(GameError::InvalidBoard));
+        assert_eq!(
+            Position::parse("2x2 ../.. --/-"),
+            Err(GameError::InvalidBoard)
+        );
+        assert_eq!(Position::parse("0x2"), Err(GameError::InvalidBoard));
+        assert_eq!(
+            Position::parse("9x1 ././././././././."),
+            Err(GameError::InvalidBoard)
+        );
+        assert_eq!(
+            Position::parse("1x9 ........."),
+            Err(GameError::InvalidBoard)
+        );
+        assert!(Position::parse(
+            "8x8 ......../......../......../......../......../......../......../........"
+        )
+        .is_ok());
+        assert_eq!(Position::parse("2by2 ../.."), Err(GameError::InvalidBoard));
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{
+    errors::GameError,
+    exec::{Cell, TileState, BOARD_SIZE},
+    grid::{Grid, Pos},
+    topology::{Surface, Topology},
+};
+
+/// The bundled puzzle pack.
+const PACK: &str = include_str!("puzzles.txt");
+
+/// A board with a fixed mine layout and some of it already played,
+/// meant to be finished by logic alone.
+///
+/// Written as a `[name]` line followed by one line per row, each tile being
+/// `.` for a safe tile, `*` for a mine, `o` for a safe tile already revealed,
+/// and `F` for a mine already flagged. Lines starting with `#` are comments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Puzzle {
+    pub name: String,
+    pub mines_map: Grid<Cell>,
+    pub board_map: Grid<TileState>,
+}
+
+impl Puzzle {
+    /// Puzzle `n` of the bundled pack, counting from 1.
+    pub fn from_pack(n: usize) -> Result<Self, GameError> {
+        Self::parse_pack(PACK)?
+            .into_iter()
+            .nth(n.checked_sub(1).ok_or(GameError::InvalidArgument)?)
+            .ok_or(GameError::InvalidArgument)
+    }
+
+    /// Number of puzzles in the bundled pack.
+    pub fn pack_len() -> usize {
+        Self::parse_pack(PACK).map_or(0, |pack| pack.len())
+    }
+
+    pub fn parse_pack(text: &str) -> Result<Vec<Self>, GameError> {
+        let mut pack = Vec::new();
+        let mut name = None;
+        let mut rows = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(next) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                if let Some(name) = name.replace(next.to_string()) {
+                    pack.push(Self::parse(name, &rows)?);
+                }
+                rows.clear();
+                continue;
+            }
+            rows.push(line);
+        }
+        match name {
+            Some(name) => pack.push(Self::parse(name, &rows)?),
+            None if rows.is_empty() => {}
+            None => return Err(GameError::InvalidPuzzle),
+        }
+        Ok(pack)
+    }
+
+    fn parse(name: String, rows: &[&str]) -> Result<Self, GameError> {
+        let cols = rows.first().map_or(0, |row| row.chars().count());
+        if cols == 0
+            || rows.len() > BOARD_SIZE.0
+            || cols > BOARD_SIZE.1
+            || rows.iter().any(|row| row.chars().count() != cols)
+        {
+            return Err(GameError::InvalidPuzzle);
+        }
+
+        let mut mines_map = Grid::new(rows.len(), cols, Cell::default());
+        let mut board_map = Grid::new(rows.len(), cols, TileState::Concealed);
+        for (i, row) in rows.iter().enumerate() {
+            for (j, tile) in row.chars().enumerate() {
+                let (mine, state) = match tile {
+                    '.' => (false, TileState::Concealed),
+                    '*' => (true, TileState::Concealed),
+                    'o' => (false, TileState::Stepped),
+                    'F' => (true, TileState::Flagged(1)),
+                    _ => return Err(GameError::InvalidPuzzle),
+                };
+                mines_map[(i, j)].mines = mine as u8;
+                board_map[(i, j)] = state;
+            }
+        }
+        for pos in mines_map.positions() {
+            mines_map[pos].adjacent = neighbours(pos, mines_map.size())
+                .map(|n| mines_map[n].mines)
+                .sum();
+        }
+
+        Ok(Self {
+            name,
+            mines_map,
+            board_map,
+        })
+    }
+
+    /// Check the revealed tiles and flags agree with the layout, and that every
+    /// safe tile can be told apart from the mines by logic, so the solution is unique.
+    pub fn validate(&self) -> Result<(), GameError> {
+        // what is known of each tile, `Some(true)` for a mine
+        let (rows, cols) = self.board_map.size();
+        let mut known = Grid::new(rows, cols, None);
+        for pos in self.board_map.positions() {
+            let mine = self.mines_map[pos].is_mine();
+            known[pos] = match self.board_map[pos] {
+                TileState::Stepped if mine => return Err(GameError::InvalidPuzzle),
+                TileState::Flagged(_) if !mine => return Err(GameError::InvalidPuzzle),
+                TileState::Stepped => Some(false),
+                TileState::Flagged(_) => Some(true),
+                _ => None,
+            };
+        }
+
+        while self.deduce(&mut known) {}
+
+        let solved = self
+            .mines_map
+            .positions()
+            .all(|pos| self.mines_map[pos].is_mine() || known[pos] == Some(false));
+        if !solved {
+            return Err(GameError::UnsolvablePuzzle);
+        }
+        Ok(())
+    }
+
+    // every revealed number gives the count of mines among its unknown neighbours
+    fn constraints(&self, known: &Grid<Option<bool>>) -> Vec<(Vec<Pos>, usize)> {
+        let size = known.size();
+        known
+            .positions()
+            .filter(|pos| known[*pos] == Some(false))
+            .filter_map(|pos| {
+                let unknown = neighbours(pos, size)
+                    .filter(|n| known[*n].is_none())
+                    .collect::<Vec<_>>();
+                let found = neighbours(pos, size)
+                    .filter(|n| known[*n] == Some(true))
+                    .count();
+                let mines = self.mines_map[pos].adjacent as usize - found;
+                (!unknown.is_empty()).then_some((unknown, mines))
+            })
+            .collect()
+    }
+
+    // settle the tiles a single number, or a number against another one whose
+    // unknown neighbours it includes, leaves no choice for; false once stuck
+    fn deduce(&self, known: &mut Grid<Option<bool>>) -> bool {
+        let constraints = self.constraints(known);
+        let mut progress = false;
+        for (tiles, mines) in constraints.iter() {
+            progress |= settle(tiles, *mines, known);
+        }
+        if progress {
+            return true;
+        }
+
+        for (inner, inner_mines) in constraints.iter() {
+            for (outer, outer_mines) in constraints.iter() {
+                if inner.len() >= outer.len() || !inner.iter().all(|pos| outer.contains(pos)) {
+                    continue;
+                }
+                let rest = outer
+                    .iter()
+                    .filter(|pos| !inner.contains(pos))
+                    .copied()
+                    .collect::<Vec<_>>();
+                progress |= settle(&rest, outer_mines - inner_mines, known);
+            }
+        }
+        progress
+    }
+}
+
+// tiles which hold either no mine or nothing but mines, false when they could be either
+fn settle(tiles: &[Pos], mines: usize, known: &mut Grid<Option<bool>>) -> bool {
+    if mines != 0 && mines != tiles.len() || tiles.iter().all(|pos| known[*pos].is_some()) {
+        return false;
+    }
+    for pos in tiles {
+        known[*pos] = Some(mines > 0);
+    }
+    true
+}
+
+// puzzles are played on the classic board
+fn neighbours(pos: Pos, size: (usize, usize)) -> impl Iterator<Item = Pos> {
+    let topology: &'static dyn Topology = Surface::Flat.topology();
+    topology.neighbours(topology.surrounding(), pos, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_bundled_puzzle_is_solvable() {
+        assert!(Puzzle::pack_len() > 0);
+        for n in 1..=Puzzle::pack_len() {
+            let puzzle = Puzzle::from_pack(n).unwrap();
+            assert_eq!(puzzle.validate(), Ok(()), "{}", puzzle.name);
+        }
+    }
+
+    #[test]
+    fn pack_is_counted_from_one() {
+        assert_eq!(Puzzle::from_pack(0), Err(GameError::InvalidArgument));
+        assert_eq!(
+            Puzzle::from_pack(Puzzle::pack_len() + 1),
+            Err(GameError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn guessing_is_refused() {
+        // nothing revealed, the mine could be anywhere
+        let pack = Puzzle::parse_pack("[guess]\n*.\n..").unwrap();
+        assert_eq!(pack[0].validate(), Err(GameError::UnsolvablePuzzle));
+        // the revealed 1 can't tell the two tiles below apart
+        let pack = Puzzle::parse_pack("[coin]\noo\n*.").unwrap();
+        assert_eq!(pack[0].validate(), Err(GameError::UnsolvablePuzzle));
+    }
+
+    #[test]
+    fn logic_alone_is_accepted() {
+        let pack = Puzzle::parse_pack("[corner]\n*o\noo").unwrap();
+        assert_eq!(pack[0].validate(), Ok(()));
+    }
+
+    #[test]
+    fn flags_count_as_known_mines() {
+        let pack = Puzzle::parse_pack("[flagged]\nFo\noo").unwrap();
+        assert_eq!(pack[0].validate(), Ok(()));
+    }
+
+    #[test]
+    fn ragged_rows_are_invalid() {
+        assert_eq!(
+            Puzzle::parse_pack("[ragged]\n*\n.."),
+            Err(GameError::InvalidPuzzle)
+        );
+        assert_eq!(
+            Puzzle::parse_pack("[unknown]\n*?"),
+            Err(GameError::InvalidPuzzle)
+        );
+    }
+}
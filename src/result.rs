@@ -0,0 +1,29 @@
+use crate::rules::GameMode;
+
+/// Summary of the current game, final once the game is won or lost.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GameResult {
+    pub mode: GameMode,
+    /// Flags placed by the auto-flag assist instead of the player.
+    pub assisted_flags: usize,
+    /// Play time when the game was won or lost, in milliseconds.
+    pub elapsed: Option<u64>,
+    /// Countdown left when the game was won or lost, in milliseconds.
+    pub remaining: Option<u64>,
+    /// Countdown gained from safe steps in time attack, in milliseconds.
+    pub time_bonus: u64,
+    /// Countdown lost to wrong flags and refused chords in penalty mode, in milliseconds.
+    pub time_penalty: u64,
+    /// Mines stepped on in lives mode, the last one included when it cost the game.
+    pub hits: usize,
+    /// Boards completed in zen mode.
+    pub cleared: usize,
+    /// Safe tiles stepped on in endless mode, its score.
+    pub explored: usize,
+}
+
+impl GameResult {
+    pub fn assisted(&self) -> bool {
+        self.assisted_flags > 0
+    }
+}
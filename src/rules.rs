@@ -0,0 +1,225 @@
+use std::iter::Peekable;
+
+use crate::{
+    errors::GameError,
+    topology::{Kernel, Surface},
+};
+
+/// Countdown gained for every safe step in time attack, in milliseconds.
+pub const TIME_BONUS: u64 = 3_000;
+/// Countdown lost for a wrong flag or a refused chord in penalty mode, in milliseconds.
+pub const TIME_PENALTY: u64 = 10_000;
+/// Lives given by `lives` when no count follows it.
+pub const DEFAULT_LIVES: u8 = 3;
+/// Mines a tile may hold with `multi` when no count follows it.
+pub const DEFAULT_MINES_PER_TILE: u8 = 2;
+/// Most mines a tile may hold.
+pub const MAX_MINES_PER_TILE: u8 = 4;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameMode {
+    #[default]
+    Classic,
+    /// Every safe step adds to the countdown.
+    TimeAttack,
+    /// Wrong flags and refused chords take from the countdown.
+    Penalty,
+    /// No clock and no losing, stepped mines are flagged and cleared boards dealt anew.
+    Zen,
+    /// A board without edges, scored by the tiles cleared before a mine goes off.
+    Endless,
+    /// The given puzzle of the bundled pack.
+    Puzzle(usize),
+    /// The classic board of the day, dealt the same to everyone.
+    Daily,
+}
+
+impl GameMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "classic",
+            GameMode::TimeAttack => "time-attack",
+            GameMode::Penalty => "penalty",
+            GameMode::Zen => "zen",
+            GameMode::Endless => "endless",
+            GameMode::Puzzle(_) => "puzzle",
+            GameMode::Daily => "daily",
+        }
+    }
+}
+
+/// Rule set a new game is played with, picked with the options of `start`/`restart`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rules {
+    pub mode: GameMode,
+    /// Detonations the player can take before losing, `None` for the classic single one.
+    pub lives: Option<u8>,
+    pub surface: Surface,
+    pub kernel: Kernel,
+    /// Most mines a single tile may hold, 1 for the classic board.
+    pub mines_per_tile: u8,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            mode: GameMode::default(),
+            lives: None,
+            surface: Surface::default(),
+            kernel: Kernel::default(),
+            mines_per_tile: 1,
+        }
+    }
+}
+
+impl Rules {
+    /// Current rules changed by the given options, e.g. `restart time-attack lives 5 torus`.
+    pub fn with_options(&self, options: &[&str]) -> Result<Self, GameError> {
+        let mut rules = self.clone();
+        let mut options = options.iter().peekable();
+        while let Some(option) = options.next() {
+            match *option {
+                "classic" => rules = Rules::default(),
+                "time-attack" | "attack" => rules.mode = GameMode::TimeAttack,
+                "penalty" => rules.mode = GameMode::Penalty,
+                "zen" => rules.mode = GameMode::Zen,
+                "endless" => rules.mode = GameMode::Endless,
+                "flat" => rules.surface = Surface::Flat,
+                "torus" => rules.surface = Surface::Torus,
+                "cylinder" => rules.surface = Surface::Cylinder,
+                "vertical-cylinder" => rules.surface = Surface::VerticalCylinder,
+                "hex" => rules.surface = Surface::Hex,
+                "surrounding" => rules.kernel = Kernel::Surrounding,
+                "knight" => rules.kernel = Kernel::Knight,
+                "cross" | "orthogonal" => rules.kernel = Kernel::Cross,
+                "radius-2" => rules.kernel = Kernel::Radius2,
+                "lives" => {
+                    rules.lives = match Self::count(&mut options).unwrap_or(DEFAULT_LIVES) {
+                        0 => return Err(GameError::InvalidArgument),
+                        count => Some(count),
+                    };
+                }
+                "multi" => {
+                    rules.mines_per_tile =
+                        match Self::count(&mut options).unwrap_or(DEFAULT_MINES_PER_TILE) {
+                            count @ 1..=MAX_MINES_PER_TILE => count,
+                            _ => return Err(GameError::InvalidArgument),
+                        };
+                }
+                _ => return Err(GameError::InvalidArgument),
+            }
+        }
+        // the kernels are laid out on square tiles, axial coordinates would scatter them
+        if rules.surface == Surface::Hex && rules.kernel != Kernel::Surrounding {
+            return Err(GameError::InvalidArgument);
+        }
+        // the endless board has no edges to wrap and a single mine per tile
+        if rules.mode == GameMode::Endless
+            && (rules.surface != Surface::Flat || rules.mines_per_tile != 1)
+        {
+            return Err(GameError::InvalidArgument);
+        }
+        // a puzzle is solved on the board it was written for
+        if matches!(rules.mode, GameMode::Puzzle(_))
+            && (rules.surface != Surface::Flat
+                || rules.kernel != Kernel::Surrounding
+                || rules.mines_per_tile != 1)
+        {
+            return Err(GameError::InvalidArgument);
+        }
+        // the daily is the same board and the same game for everyone
+        if rules.mode == GameMode::Daily
+            && (rules.surface != Surface::Flat
+                || rules.kernel != Kernel::Surrounding
+                || rules.mines_per_tile != 1
+                || rules.lives.is_some())
+        {
+            return Err(GameError::InvalidArgument);
+        }
+        Ok(rules)
+    }
+
+    // take the number following an option, if there is one
+    fn count<'a>(options: &mut Peekable<impl Iterator<Item = &'a &'a str>>) -> Option<u8> {
+        let count = options.peek().and_then(|count| count.parse::<u8>().ok());
+        if count.is_some() {
+            options.next();
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_change_the_current_rules() {
+        let rules = Rules::default()
+            .with_options(&["penalty", "torus", "knight", "lives", "multi", "3"])
+            .unwrap();
+        assert_eq!(rules.mode, GameMode::Penalty);
+        assert_eq!(rules.surface, Surface::Torus);
+        assert_eq!(rules.kernel, Kernel::Knight);
+        assert_eq!(rules.lives, Some(DEFAULT_LIVES));
+        assert_eq!(rules.mines_per_tile, 3);
+        assert_eq!(rules.with_options(&["classic"]), Ok(Rules::default()));
+        assert!(rules.with_options(&["multi", "9"]).is_err());
+    }
+
+    #[test]
+    fn hex_takes_no_square_kernels() {
+        for kernel in ["knight", "cross", "radius-2"] {
+            assert!(Rules::default().with_options(&["hex", kernel]).is_err());
+            assert!(Rules::default().with_options(&[kernel, "hex"]).is_err());
+        }
+        let hex = Rules::default().with_options(&["hex"]).unwrap();
+        assert!(hex.with_options(&["knight"]).is_err());
+        assert!(hex.with_options(&["flat", "knight"]).is_ok());
+    }
+
+    #[test]
+    fn endless_takes_only_what_it_can_play() {
+        let endless = Rules::default().with_options(&["endless"]).unwrap();
+        assert!(endless.with_options(&["knight", "lives", "2"]).is_ok());
+        assert!(endless.with_options(&["torus"]).is_err());
+        assert!(endless.with_options(&["multi", "3"]).is_err());
+        assert!(Rules::default()
+            .with_options(&["torus", "multi", "endless"])
+            .is_err());
+        assert!(endless.with_options(&["classic", "torus"]).is_ok());
+    }
+
+    #[test]
+    fn puzzles_keep_the_board_they_were_written_for() {
+        let puzzle = Rules {
+            mode: GameMode::Puzzle(1),
+            ..Rules::default()
+        };
+        for options in [&["torus"][..], &["hex"], &["knight"], &["multi", "3"]] {
+            assert!(puzzle.with_options(options).is_err(), "{:?}", options);
+        }
+        assert_eq!(
+            puzzle.with_options(&["lives", "2"]).map(|rules| rules.mode),
+            Ok(GameMode::Puzzle(1))
+        );
+        assert!(puzzle.with_options(&["classic", "torus"]).is_ok());
+    }
+
+    #[test]
+    fn the_daily_takes_no_variants() {
+        let daily = Rules {
+            mode: GameMode::Daily,
+            ..Rules::default()
+        };
+        for options in [&["torus"][..], &["knight"], &["multi", "2"], &["lives"]] {
+            assert!(daily.with_options(options).is_err(), "{:?}", options);
+        }
+        assert_eq!(
+            daily
+                .with_options(&["classic", "torus"])
+                .map(|rules| rules.mode),
+            Ok(GameMode::Classic)
+        );
+    }
+}
@@ -0,0 +1,346 @@
+use std::{cmp::Reverse, collections::BTreeMap};
+
+use serde::{Deserialize, Serialize};
+use yewdux::storage::{self, Area};
+
+use crate::{errors::GameError, keymap::Keymap};
+
+pub const DEFAULT_PROFILE: &str = "default";
+const MAX_MACRO_DEPTH: usize = 8;
+const RESERVED_WORDS: [&str; 18] = [
+    "alias", "unalias", "macro", "unmacro", "profile", "start", "restart", "reset", "quit", "exit",
+    "settings", "pause", "resume", "pan", "puzzle", "daily", "import", "export",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub profile: String,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            profile: DEFAULT_PROFILE.to_string(),
+            profiles: BTreeMap::from([(DEFAULT_PROFILE.to_string(), Profile::default())]),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        storage::load::<Self>(Area::Local)
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        // losing the preferences is not worth interrupting the game for
+        let _ = storage::save(self, Area::Local);
+    }
+
+    pub fn active(&self) -> &Profile {
+        self.profiles
+            .get(&self.profile)
+            .expect("active profile should always exist")
+    }
+
+    pub fn active_mut(&mut self) -> &mut Profile {
+        self.profiles
+            .get_mut(&self.profile)
+            .expect("active profile should always exist")
+    }
+
+    pub fn define(&mut self, def: &Definition) {
+        match def {
+            Definition::Alias(name, target) => {
+                self.active_mut()
+                    .aliases
+                    .insert(name.clone(), target.clone());
+            }
+            Definition::Unalias(name) => {
+                self.active_mut().aliases.remove(name);
+            }
+            Definition::Macro(name, m) => {
+                self.active_mut().macros.insert(name.clone(), m.clone());
+            }
+            Definition::Unmacro(name) => {
+                self.active_mut().macros.remove(name);
+            }
+            Definition::Profile(name) => {
+                self.profiles.entry(name.clone()).or_default();
+                self.profile = name.clone();
+            }
+        }
+        self.save();
+    }
+
+    /// Update the active profile and persist it.
+    pub fn update(&mut self, f: impl FnOnce(&mut Profile)) {
+        f(self.active_mut());
+        self.save();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub aliases: BTreeMap<String, String>,
+    pub macros: BTreeMap<String, Macro>,
+    pub keymap: Keymap,
+    /// Chord regardless of the flags around the number.
+    pub aggressive_chord: bool,
+    /// Let toggling a flag go through a question mark before clearing it.
+    pub question_marks: bool,
+    /// Flag the tiles which can only be mines, recorded as assisted in the result.
+    pub auto_flag: bool,
+    /// Pause the game when the window loses focus or the tab gets hidden.
+    pub auto_pause: bool,
+    pub timer_mode: TimerMode,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            aliases: BTreeMap::new(),
+            macros: BTreeMap::new(),
+            keymap: Keymap::default(),
+            aggressive_chord: false,
+            question_marks: false,
+            auto_flag: false,
+            auto_pause: true,
+            timer_mode: TimerMode::default(),
+        }
+    }
+}
+
+impl Profile {
+    /// Replace an aliased command or command verb with its target,
+    /// `x` in `xa1` or `x a1` will be resolved, as well as the whole `x` command.
+    pub fn resolve_alias(&self, cmd: &str) -> String {
+        let cmd = cmd.trim();
+        if let Some(target) = self.aliases.get(cmd) {
+            return target.clone();
+        }
+
+        let (verb, sep, rest) = match cmd.split_once(char::is_whitespace) {
+            Some((verb, rest)) => (verb, " ", rest),
+            // only glued with a coordinate, so `r` won't turn `restart` into `resetestart`
+            None if cmd.chars().any(|c| c.is_ascii_digit()) => {
+                let (verb, rest) = cmd.split_at(cmd.chars().next().map_or(0, char::len_utf8));
+                (verb, "", rest)
+            }
+            None => return cmd.to_string(),
+        };
+        match self.aliases.get(verb) {
+            Some(target) => format!("{}{}{}", target, sep, rest),
+            None => cmd.to_string(),
+        }
+    }
+
+    /// Expand macro invocation into the list of commands it stands for,
+    /// non macro commands are returned as is.
+    pub fn expand(&self, cmd: &str) -> Result<Vec<String>, GameError> {
+        self.expand_at(cmd, 0)
+    }
+
+    fn expand_at(&self, cmd: &str, depth: usize) -> Result<Vec<String>, GameError> {
+        let cmd = cmd.trim().to_lowercase();
+        let mut words = cmd.split_whitespace();
+        let Some(name) = words.next() else {
+            return Ok(Vec::new());
+        };
+        let Some(m) = self.macros.get(name) else {
+            return Ok(vec![cmd]);
+        };
+        if depth >= MAX_MACRO_DEPTH {
+            return Err(GameError::RecursiveMacro);
+        }
+
+        let args = words.collect::<Vec<&str>>();
+        if args.len() != m.params.len() {
+            return Err(GameError::InvalidArgument);
+        }
+
+        let mut commands = Vec::new();
+        for cmd in self.split_commands(&m.substitute(&args)) {
+            commands.extend(self.expand_at(&cmd, depth + 1)?);
+        }
+        Ok(commands)
+    }
+
+    // macro body is a sequence of whitespace separated commands,
+    // a lone verb like `s` is joined with the coordinate following it.
+    fn split_commands(&self, body: &str) -> Vec<String> {
+        let mut commands = Vec::new();
+        let mut words = body.split_whitespace().peekable();
+        while let Some(word) = words.next() {
+            match words.peek() {
+                Some(arg) if self.is_verb(word) => {
+                    commands.push(format!("{} {}", word, arg));
+                    words.next();
+                }
+                _ => commands.push(word.to_string()),
+            }
+        }
+        commands
+    }
+
+    fn is_verb(&self, word: &str) -> bool {
+        let resolved = self.resolve_alias(word);
+        resolved.chars().count() == 1 && resolved.chars().all(|c| c.is_ascii_alphabetic())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimerMode {
+    /// Time left before the mines go off.
+    #[default]
+    Countdown,
+    /// Classic play time, in seconds and tenths.
+    Elapsed,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Macro {
+    pub params: Vec<String>,
+    pub body: String,
+}
+
+impl Macro {
+    fn substitute(&self, args: &[&str]) -> String {
+        let mut params = self.params.iter().zip(args.iter()).collect::<Vec<_>>();
+        // longest name first, so `$row` is not clobbered by `$r`
+        params.sort_by_key(|(param, _)| Reverse(param.len()));
+        params
+            .into_iter()
+            .fold(self.body.clone(), |body, (param, arg)| {
+                body.replace(&format!("${}", param), arg)
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Definition {
+    Alias(String, String),
+    Unalias(String),
+    Macro(String, Macro),
+    Unmacro(String),
+    Profile(String),
+}
+
+impl Definition {
+    /// Parse `alias x = t`, `unalias x`, `macro name params.. = body`,
+    /// `unmacro name`, and `profile name`. Returns `None` for any other command.
+    pub fn parse(cmd: &str) -> Result<Option<Self>, GameError> {
+        let (head, body) = match cmd.split_once('=') {
+            Some((head, body)) => (head, Some(body.trim())),
+            None => (cmd, None),
+        };
+        let mut words = head.split_whitespace();
+        let keyword = words.next().unwrap_or_default();
+        let names = words.collect::<Vec<&str>>();
+
+        let def = match (keyword, names.as_slice(), body) {
+            ("alias", [name], Some(target)) if !target.is_empty() => {
+                Definition::Alias(Self::name(name)?, target.to_string())
+            }
+            ("unalias", [name], None) => Definition::Unalias(name.to_string()),
+            ("macro", [name, params @ ..], Some(body)) if !body.is_empty() => Definition::Macro(
+                Self::name(name)?,
+                Macro {
+                    params: params.iter().map(|p| p.to_string()).collect(),
+                    body: body.to_string(),
+                },
+            ),
+            ("unmacro", [name], None) => Definition::Unmacro(name.to_string()),
+            ("profile", [name], None) => Definition::Profile(name.to_string()),
+            ("alias" | "unalias" | "macro" | "unmacro" | "profile", _, _) => {
+                return Err(GameError::InvalidArgument)
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(def))
+    }
+
+    fn name(name: &str) -> Result<String, GameError> {
+        if RESERVED_WORDS.contains(&name) {
+            return Err(GameError::InvalidArgument);
+        }
+        Ok(name.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(defs: &[&str]) -> Profile {
+        let mut profile = Profile::default();
+        for def in defs {
+            match Definition::parse(def).unwrap().unwrap() {
+                Definition::Alias(name, target) => {
+                    profile.aliases.insert(name, target);
+                }
+                Definition::Macro(name, m) => {
+                    profile.macros.insert(name, m);
+                }
+                def => panic!("{:?} is not a verb", def),
+            }
+        }
+        profile
+    }
+
+    #[test]
+    fn aliases_resolve_the_verb_or_the_whole_command() {
+        let profile = profile(&["alias x = t", "alias r = reset"]);
+        assert_eq!(profile.resolve_alias("xa1"), "ta1");
+        assert_eq!(profile.resolve_alias("x a1"), "t a1");
+        assert_eq!(profile.resolve_alias("r"), "reset");
+        assert_eq!(profile.resolve_alias("restart"), "restart");
+    }
+
+    #[test]
+    fn macros_substitute_their_params() {
+        let profile = profile(&["macro row r = s a$r s b$r", "macro two r rr = s a$rr s b$r"]);
+        assert_eq!(profile.expand("row 3").unwrap(), ["s a3", "s b3"]);
+        // the longer name is replaced first
+        assert_eq!(profile.expand("two 1 2").unwrap(), ["s a2", "s b1"]);
+        assert_eq!(profile.expand("row"), Err(GameError::InvalidArgument));
+        assert_eq!(profile.expand("sa1").unwrap(), ["sa1"]);
+    }
+
+    #[test]
+    fn macros_nest_up_to_the_depth_limit() {
+        let nested = profile(&["macro inner = sa1", "macro outer = inner sb2"]);
+        assert_eq!(nested.expand("outer").unwrap(), ["sa1", "sb2"]);
+
+        let looping = profile(&["macro ping = pong", "macro pong = ping"]);
+        assert_eq!(looping.expand("ping"), Err(GameError::RecursiveMacro));
+    }
+
+    #[test]
+    fn reserved_words_cant_be_redefined() {
+        for word in RESERVED_WORDS {
+            assert_eq!(
+                Definition::parse(&format!("alias {} = s", word)),
+                Err(GameError::InvalidArgument)
+            );
+            assert_eq!(
+                Definition::parse(&format!("macro {} = sa1", word)),
+                Err(GameError::InvalidArgument)
+            );
+        }
+        assert_eq!(
+            Definition::parse("unalias x"),
+            Ok(Some(Definition::Unalias("x".to_string())))
+        );
+        assert_eq!(
+            Definition::parse("alias x ="),
+            Err(GameError::InvalidArgument)
+        );
+        assert_eq!(Definition::parse("sa1"), Ok(None));
+    }
+}
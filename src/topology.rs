@@ -0,0 +1,356 @@
+use crate::grid::Pos;
+
+/// Offsets of the 8 tiles surrounding a tile, as `(row, column)`.
+const SURROUNDING: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// Offsets of the 6 tiles around a hex tile, the board rows being the axial `r`
+/// and the columns the axial `q`.
+const HEX_SURROUNDING: [(isize, isize); 6] = [(-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0)];
+
+const KNIGHT: [(isize, isize); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+
+const CROSS: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+const RADIUS_2: [(isize, isize); 24] = [
+    (-2, -2),
+    (-2, -1),
+    (-2, 0),
+    (-2, 1),
+    (-2, 2),
+    (-1, -2),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (-1, 2),
+    (0, -2),
+    (0, -1),
+    (0, 1),
+    (0, 2),
+    (1, -2),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (1, 2),
+    (2, -2),
+    (2, -1),
+    (2, 0),
+    (2, 1),
+    (2, 2),
+];
+
+/// How the tiles of a `rows` x `cols` board connect to each other.
+pub trait Topology {
+    /// Board position of row `i` and column `j`, which may be past an edge,
+    /// `None` when there is no tile there.
+    fn wrap(&self, i: isize, j: isize, rows: usize, cols: usize) -> Option<(usize, usize)>;
+
+    /// Offsets of the tiles touching a tile, as `(row, column)`.
+    fn surrounding(&self) -> &'static [(isize, isize)] {
+        &SURROUNDING
+    }
+}
+
+impl dyn Topology {
+    /// Tiles `offsets` away from `pos` on a board of `size`.
+    pub fn neighbours<'a>(
+        &'a self,
+        offsets: &'a [(isize, isize)],
+        pos: Pos,
+        size: (usize, usize),
+    ) -> Neighbours<'a> {
+        Neighbours {
+            topology: self,
+            offsets,
+            pos,
+            size,
+            // offsets reach at most 2 tiles away, so only boards up to 4 tiles
+            // across can have two of them land on the same tile
+            small: size.0 <= 4 || size.1 <= 4,
+            // and tiles further than that from the edges need no wrapping
+            inner: pos.0 >= 2 && pos.1 >= 2 && pos.0 + 2 < size.0 && pos.1 + 2 < size.1,
+            next: 0,
+        }
+    }
+}
+
+/// Iterator over the neighbours of a tile, each one only once even when
+/// the board is small enough for the edges to meet.
+pub struct Neighbours<'a> {
+    topology: &'a dyn Topology,
+    offsets: &'a [(isize, isize)],
+    pos: Pos,
+    size: (usize, usize),
+    small: bool,
+    inner: bool,
+    next: usize,
+}
+
+impl Neighbours<'_> {
+    fn at(&self, (di, dj): (isize, isize)) -> Option<Pos> {
+        let (i, j) = self.pos;
+        if self.inner {
+            return Some((i.wrapping_add_signed(di), j.wrapping_add_signed(dj)));
+        }
+        let (rows, cols) = self.size;
+        self.topology
+            .wrap(i as isize + di, j as isize + dj, rows, cols)
+    }
+}
+
+impl Iterator for Neighbours<'_> {
+    type Item = Pos;
+
+    fn next(&mut self) -> Option<Pos> {
+        while let Some(offset) = self.offsets.get(self.next) {
+            self.next += 1;
+            let Some(n) = self.at(*offset) else {
+                continue;
+            };
+            let seen = self.small
+                && (n == self.pos
+                    || self.offsets[..self.next - 1]
+                        .iter()
+                        .any(|earlier| self.at(*earlier) == Some(n)));
+            if !seen {
+                return Some(n);
+            }
+        }
+        None
+    }
+}
+
+// keep `n` within `0..len`, going around when `wraps`
+fn axis(n: isize, len: usize, wraps: bool) -> Option<usize> {
+    match n {
+        _ if wraps => Some(n.rem_euclid(len as isize) as usize),
+        n if n >= 0 && (n as usize) < len => Some(n as usize),
+        _ => None,
+    }
+}
+
+/// The classic board, tiles on the edges have fewer neighbours.
+pub struct Flat;
+
+/// Left and right edges meet, as do the top and bottom ones.
+pub struct Torus;
+
+/// Left and right edges meet.
+pub struct Cylinder;
+
+/// Top and bottom edges meet.
+pub struct VerticalCylinder;
+
+/// Hexagonal tiles in axial coordinates, laid out as a rhombus.
+pub struct Hex;
+
+impl Topology for Flat {
+    fn wrap(&self, i: isize, j: isize, rows: usize, cols: usize) -> Option<(usize, usize)> {
+        Some((axis(i, rows, false)?, axis(j, cols, false)?))
+    }
+}
+
+impl Topology for Torus {
+    fn wrap(&self, i: isize, j: isize, rows: usize, cols: usize) -> Option<(usize, usize)> {
+        Some((axis(i, rows, true)?, axis(j, cols, true)?))
+    }
+}
+
+impl Topology for Cylinder {
+    fn wrap(&self, i: isize, j: isize, rows: usize, cols: usize) -> Option<(usize, usize)> {
+        Some((axis(i, rows, false)?, axis(j, cols, true)?))
+    }
+}
+
+impl Topology for VerticalCylinder {
+    fn wrap(&self, i: isize, j: isize, rows: usize, cols: usize) -> Option<(usize, usize)> {
+        Some((axis(i, rows, true)?, axis(j, cols, false)?))
+    }
+}
+
+impl Topology for Hex {
+    fn wrap(&self, i: isize, j: isize, rows: usize, cols: usize) -> Option<(usize, usize)> {
+        Some((axis(i, rows, false)?, axis(j, cols, false)?))
+    }
+
+    fn surrounding(&self) -> &'static [(isize, isize)] {
+        &HEX_SURROUNDING
+    }
+}
+
+/// Topology a game is played on, picked with the options of `start`/`restart`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Surface {
+    #[default]
+    Flat,
+    Torus,
+    Cylinder,
+    VerticalCylinder,
+    Hex,
+}
+
+impl Surface {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Surface::Flat => "flat",
+            Surface::Torus => "torus",
+            Surface::Cylinder => "cylinder",
+            Surface::VerticalCylinder => "vertical-cylinder",
+            Surface::Hex => "hex",
+        }
+    }
+
+    pub fn topology(&self) -> &'static dyn Topology {
+        match self {
+            Surface::Flat => &Flat,
+            Surface::Torus => &Torus,
+            Surface::Cylinder => &Cylinder,
+            Surface::VerticalCylinder => &VerticalCylinder,
+            Surface::Hex => &Hex,
+        }
+    }
+}
+
+/// Which tiles count as neighbours, picked with the options of `start`/`restart`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kernel {
+    /// The tiles touching a tile, as the surface lays them out.
+    #[default]
+    Surrounding,
+    /// The tiles a chess knight can jump to.
+    Knight,
+    /// Only the tiles sharing an edge, no diagonals.
+    Cross,
+    /// The tiles up to two steps away, diagonals included.
+    Radius2,
+}
+
+impl Kernel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Kernel::Surrounding => "surrounding",
+            Kernel::Knight => "knight",
+            Kernel::Cross => "cross",
+            Kernel::Radius2 => "radius-2",
+        }
+    }
+
+    /// Offsets of the neighbours, as `(row, column)`, `None` to leave it to the surface.
+    pub fn offsets(&self) -> Option<&'static [(isize, isize)]> {
+        match self {
+            Kernel::Surrounding => None,
+            Kernel::Knight => Some(&KNIGHT),
+            Kernel::Cross => Some(&CROSS),
+            Kernel::Radius2 => Some(&RADIUS_2),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SURFACES: [Surface; 5] = [
+        Surface::Flat,
+        Surface::Torus,
+        Surface::Cylinder,
+        Surface::VerticalCylinder,
+        Surface::Hex,
+    ];
+
+    // the neighbours as the surface places them, without the fast paths
+    fn expected(surface: Surface, offsets: &[(isize, isize)], pos: Pos, size: Pos) -> Vec<Pos> {
+        let mut expected = Vec::new();
+        for (di, dj) in offsets {
+            let n =
+                surface
+                    .topology()
+                    .wrap(pos.0 as isize + di, pos.1 as isize + dj, size.0, size.1);
+            if let Some(n) = n.filter(|n| *n != pos && !expected.contains(n)) {
+                expected.push(n);
+            }
+        }
+        expected
+    }
+
+    fn check(size: Pos) {
+        for surface in SURFACES {
+            let topology = surface.topology();
+            for offsets in [topology.surrounding(), &KNIGHT, &CROSS, &RADIUS_2] {
+                for i in 0..size.0 {
+                    for j in 0..size.1 {
+                        let found = topology
+                            .neighbours(offsets, (i, j), size)
+                            .collect::<Vec<_>>();
+                        assert_eq!(
+                            found,
+                            expected(surface, offsets, (i, j), size),
+                            "{:?} {:?} at {:?}",
+                            surface,
+                            size,
+                            (i, j)
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn small_boards_give_each_neighbour_once() {
+        for size in [(1, 1), (2, 2), (4, 4), (1, 4), (2, 5), (5, 3)] {
+            check(size);
+        }
+    }
+
+    #[test]
+    fn inner_tiles_match_the_wrapped_path() {
+        for size in [(5, 5), (7, 9), (12, 12)] {
+            check(size);
+        }
+    }
+
+    #[test]
+    fn torus_corner_wraps_around() {
+        let torus = Surface::Torus.topology();
+        let mut found = torus
+            .neighbours(torus.surrounding(), (0, 0), (4, 4))
+            .collect::<Vec<_>>();
+        found.sort();
+        assert_eq!(
+            found,
+            [
+                (0, 1),
+                (0, 3),
+                (1, 0),
+                (1, 1),
+                (1, 3),
+                (3, 0),
+                (3, 1),
+                (3, 3)
+            ]
+        );
+        let flat = Surface::Flat.topology();
+        assert_eq!(
+            flat.neighbours(flat.surrounding(), (0, 0), (1, 1)).count(),
+            0
+        );
+    }
+}